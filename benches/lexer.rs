@@ -0,0 +1,56 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lexer_analyzer::lexer::compact::tokenize_compact;
+use lexer_analyzer::lexer::tokenize;
+
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+// generated programs of increasing size, reported in bytes per second
+fn programs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tokenize_program");
+    for (name, size) in [("1kb", 1024), ("100kb", 100 * 1024), ("5mb", 5 * 1024 * 1024)] {
+        let source = support::program(size, 42);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &source, |b, source| {
+            b.iter(|| tokenize(black_box(source)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+// same 5mb program into compact tokens, 12 bytes each instead of 128 for a full
+// `Token` (see benches/baseline.txt)
+fn compact(c: &mut Criterion) {
+    let source = support::program(5 * 1024 * 1024, 42);
+    let mut group = c.benchmark_group("tokenize_compact");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.bench_with_input(BenchmarkId::from_parameter("5mb"), &source, |b, source| {
+        b.iter(|| tokenize_compact(black_box(source)).unwrap())
+    });
+    group.finish();
+}
+
+// inputs at the extremes: a single huge token or a huge number of tiny ones
+fn pathological(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tokenize_pathological");
+    let inputs = [
+        ("giant_identifier", support::giant_identifier(1024 * 1024)),
+        ("giant_number", support::giant_number(1024 * 1024)),
+        ("single_char_tokens", support::single_char_tokens(100_000)),
+    ];
+    for (name, source) in &inputs {
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), source, |b, source| {
+            b.iter(|| tokenize(black_box(source)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+// many tiny inputs, where setting up the lexer dominates the scanning
+fn small_snippets(c: &mut Criterion) {
+    c.bench_function("tokenize_snippet", |b| b.iter(|| tokenize(black_box("let x = 1")).unwrap()));
+}
+
+criterion_group!(benches, programs, compact, pathological, small_snippets);
+criterion_main!(benches);
@@ -0,0 +1,75 @@
+use std::collections::VecDeque;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lexer_analyzer::lexer::tokenize;
+
+// the original scanner: the whole source is collected into a `VecDeque<char>`
+// and consumed with `pop_front`, every token owns a fresh `String`
+fn tokenize_vecdeque(source_code: &str) -> Vec<(String, u8)> {
+    let mut tokens = Vec::new();
+    let mut src: VecDeque<char> = source_code.chars().collect();
+
+    while let Some(current) = src.front().copied() {
+        match current {
+            '(' | ')' | '+' | '-' | '*' | '/' | '%' | '=' => {
+                tokens.push((String::from(current), 0));
+                src.pop_front();
+            }
+            c if c.is_numeric() => {
+                let mut num = String::new();
+                while let Some(digit) = src.front().copied().filter(|&c| c.is_numeric()) {
+                    num.push(digit);
+                    src.pop_front();
+                }
+                tokens.push((num, 1));
+            }
+            c if c.is_alphabetic() => {
+                let mut ident = String::new();
+                while let Some(letter) = src.front().copied().filter(|&c| c.is_alphabetic()) {
+                    ident.push(letter);
+                    src.pop_front();
+                }
+                let kind = if ident == "let" || ident == "null" { 2 } else { 3 };
+                tokens.push((ident, kind));
+            }
+            c if c.is_whitespace() => {
+                src.pop_front();
+            }
+            c => panic!("Unrecognized character found in source code: {:?}", c),
+        }
+    }
+    tokens.push((String::from("EndOfFile"), 4));
+    tokens
+}
+
+// roughly `size` bytes of valid source, the same every run
+fn generate_source(size: usize) -> String {
+    let lines = [
+        "let total = 45 * (4 / 3)\n",
+        "let someVariable = total % 7 + null\n",
+        "let x = (1 + 2) * (3 - 4) / 567890\n",
+    ];
+    let mut source = String::with_capacity(size);
+    let mut i = 0;
+    while source.len() < size {
+        source.push_str(lines[i % lines.len()]);
+        i += 1;
+    }
+    source
+}
+
+fn scanning(c: &mut Criterion) {
+    let source = generate_source(1024 * 1024);
+    let mut group = c.benchmark_group("scanning_1mb");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.bench_with_input(BenchmarkId::new("vecdeque", "1mb"), &source, |b, source| {
+        b.iter(|| tokenize_vecdeque(black_box(source)))
+    });
+    group.bench_with_input(BenchmarkId::new("index", "1mb"), &source, |b, source| {
+        b.iter(|| tokenize(black_box(source)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, scanning);
+criterion_main!(benches);
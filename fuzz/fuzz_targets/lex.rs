@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lexer_analyzer::lexer::{tokenize, tokenize_all, tokenize_preserving_trivia, TokenType};
+
+// run with `cargo +nightly fuzz run lex` from the repository root
+fuzz_target!(|data: &[u8]| {
+    // the lexer works on text, invalid UTF-8 is rejected before it gets there
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(tokens) = tokenize(source) {
+        assert_eq!(Some(TokenType::Eof), tokens.last().map(|token| token.kind()));
+    }
+    if let Ok(tokens) = tokenize_preserving_trivia(source) {
+        assert_eq!(Some(TokenType::Eof), tokens.last().map(|token| token.kind()));
+    }
+    let (tokens, _) = tokenize_all(source);
+    assert_eq!(Some(TokenType::Eof), tokens.last().map(|token| token.kind()));
+});
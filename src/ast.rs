@@ -0,0 +1,149 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::lexer::Span;
+
+/// A node that knows the source text it was parsed from.
+pub trait Spanned {
+    /// Covers the whole node, from its first token through its last one.
+    fn span(&self) -> Span;
+}
+
+/// Root of a parsed source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub statements: Vec<Stmt>,
+    /// Covers every statement, a program without any is the empty span at EOF.
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    /// `let pattern = value`, or `const pattern = value` when `constant`
+    Let { constant: bool, pattern: Pattern, value: Expr, span: Span },
+    /// `defer body`, the body runs when the enclosing scope exits, it is an
+    /// expression statement or a block
+    Defer { body: Box<Stmt>, span: Span },
+    Block(Block),
+    Expr(Expr),
+    /// Tokens skipped after a parse error, see
+    /// [`parse_program_recovering`](crate::parser::parse_program_recovering).
+    Error(Span),
+}
+
+/// `{ ... }`, statements in a scope of their own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub statements: Vec<Stmt>,
+    /// From the `{` through the `}`.
+    pub span: Span,
+}
+
+/// What a `let` binds its value to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// The whole value, `let x = ...`
+    Name { name: String, span: Span },
+    /// Elements by position, `let [a, b] = ...`
+    Array { elements: Vec<Pattern>, span: Span },
+    /// Fields by name, `let { x, y: [a] } = ...`
+    Object { fields: Vec<FieldPattern>, span: Span },
+}
+
+/// One field of an object pattern, `x` is short for `x: x`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldPattern {
+    pub key: String,
+    pub value: Pattern,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Eq,
+    /// `a ?? b`, `b` unless `a` is null
+    Coalesce,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `-x`
+    Neg,
+    /// `!x`
+    Not,
+}
+
+/// Expression tree. Parentheses leave no node, they only shape the tree and
+/// widen the span of the expression they wrap, so `(1 + 2)` is an `Add` whose
+/// span includes both parentheses.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    NumberLiteral { value: f64, span: Span },
+    Identifier { name: String, span: Span },
+    Unary { op: UnaryOp, operand: Box<Expr>, span: Span },
+    Binary { op: BinaryOp, lhs: Box<Expr>, rhs: Box<Expr>, span: Span },
+    /// `object?.field`, null instead of an error when `object` is null
+    OptionalMember { object: Box<Expr>, field: String, span: Span },
+    /// `target = value`, the target is a name
+    Assign { target: Box<Expr>, value: Box<Expr>, span: Span },
+    /// `value as Type`, the type is any name, whether it exists is not checked.
+    Cast { value: Box<Expr>, target_type: String, span: Span },
+}
+
+impl Spanned for Program {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl Spanned for Stmt {
+    fn span(&self) -> Span {
+        match self {
+            Stmt::Let { span, .. } | Stmt::Defer { span, .. } => *span,
+            Stmt::Block(block) => block.span(),
+            Stmt::Expr(expr) => expr.span(),
+            Stmt::Error(span) => *span,
+        }
+    }
+}
+
+impl Spanned for Block {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl Spanned for Pattern {
+    fn span(&self) -> Span {
+        match self {
+            Pattern::Name { span, .. } | Pattern::Array { span, .. } | Pattern::Object { span, .. } => *span,
+        }
+    }
+}
+
+impl Spanned for FieldPattern {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl Spanned for Expr {
+    fn span(&self) -> Span {
+        match self {
+            Expr::NumberLiteral { span, .. }
+            | Expr::Identifier { span, .. }
+            | Expr::Unary { span, .. }
+            | Expr::Binary { span, .. }
+            | Expr::OptionalMember { span, .. }
+            | Expr::Assign { span, .. }
+            | Expr::Cast { span, .. } => *span,
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::lexer::source::{line_col, line_starts, SourceFile};
+use crate::lexer::{LexError, Lexer, Span, Token, TokenType};
+
+/// Token reduced to its kind and byte range, 12 bytes instead of a full
+/// [`Token`]. The text is sliced from the source when it is needed.
+///
+/// This is a representation next to [`Token`], not a smaller `Token`: tokens
+/// from [`tokenize`](crate::lexer::tokenize) keep their full size, 128 bytes on
+/// x86_64, with their trivia and owned values. Only code that lexes with
+/// [`tokenize_compact`] gets the smaller tokens, and it expands them back with
+/// [`CompactTokens::get`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompactToken {
+    kind: TokenType,
+    start: u32,
+    end: u32,
+}
+
+impl CompactToken {
+    pub fn kind(&self) -> TokenType {
+        self.kind
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start as usize..self.end as usize
+    }
+
+    /// The token text in `source`, which must be the text it was lexed from.
+    /// Unlike [`Token::value`], line continuations are still in it.
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.range()]
+    }
+}
+
+/// Tokens of a source kept as [`CompactToken`]s.
+///
+/// Lines and columns are not stored per token, [`CompactTokens::get`] works
+/// them out from a table of line starts, one entry per line. Offsets are `u32`,
+/// so the source must be shorter than 4 GiB.
+#[derive(Debug, Clone)]
+pub struct CompactTokens<'src> {
+    source: &'src str,
+    tokens: Vec<CompactToken>,
+    // borrowed from the `SourceFile` when there is one
+    line_starts: Cow<'src, [usize]>,
+}
+
+impl<'src> CompactTokens<'src> {
+    pub fn source(&self) -> &'src str {
+        self.source
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CompactToken> {
+        self.tokens.iter()
+    }
+
+    /// Full token at `index`, the same one [`tokenize`](crate::lexer::tokenize)
+    /// would have produced.
+    pub fn get(&self, index: usize) -> Option<Token<'src>> {
+        let token = self.tokens.get(index)?;
+        let start = token.start as usize;
+        let (line, column) = line_col(self.source, &self.line_starts, start);
+        let span = Span::new(start, token.end as usize, line, column);
+        Some(Token::from_source_text(token.text(self.source), token.kind, span))
+    }
+
+    /// Expands every token back into a full [`Token`].
+    pub fn to_tokens(&self) -> Vec<Token<'src>> {
+        (0..self.len()).filter_map(|index| self.get(index)).collect()
+    }
+}
+
+/// Like [`tokenize`](crate::lexer::tokenize), but keeps the tokens compact.
+pub fn tokenize_compact(source_code: &str) -> Result<CompactTokens<'_>, LexError> {
+    compact(source_code, Cow::Owned(line_starts(source_code)))
+}
+
+/// Like [`tokenize_compact`], reusing the line index of `file` instead of
+/// building one.
+pub fn tokenize_compact_file(file: &SourceFile) -> Result<CompactTokens<'_>, LexError> {
+    compact(file.text(), Cow::Borrowed(file.line_starts()))
+}
+
+fn compact<'src>(source_code: &'src str, line_starts: Cow<'src, [usize]>) -> Result<CompactTokens<'src>, LexError> {
+    if u32::try_from(source_code.len()).is_err() {
+        return Err(LexError::source_too_large());
+    }
+    let tokens = Lexer::new(source_code)
+        .map(|result| {
+            result.map(|token| CompactToken {
+                kind: token.kind,
+                start: token.span.start as u32,
+                end: token.span.end as u32,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(CompactTokens { source: source_code, tokens, line_starts })
+}
+
+#[cfg(test)]
+mod tests{
+    use core::mem::size_of;
+
+    use crate::lexer::compact::{tokenize_compact, tokenize_compact_file, CompactToken};
+    use crate::lexer::source::SourceFile;
+    use crate::lexer::{tokenize, TokenType};
+
+    // `Token` itself is not made any smaller, see benches/baseline.txt
+    #[test]
+    fn should_keep_compact_tokens_small(){
+        assert_eq!(1, size_of::<TokenType>());
+        assert!(size_of::<CompactToken>() <= 16);
+    }
+
+    #[test]
+    fn should_expand_to_the_same_tokens_as_tokenize(){
+        let sources = ["let x = 45 * (4 / 3)", "let é = \"a{b}c\"\r\nx\rnull\n\n// done\n", "\"a\\\n  b\" `c\nd`", ""];
+        for source in sources {
+            let compact = tokenize_compact(source).unwrap();
+            assert_eq!(tokenize(source).unwrap(), compact.to_tokens(), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn should_expand_tokens_of_a_source_file(){
+        let file = SourceFile::new("main.lang", "let x\r\n(é)\n");
+        let compact = tokenize_compact_file(&file).unwrap();
+        assert_eq!(tokenize(file.text()).unwrap(), compact.to_tokens());
+    }
+
+    #[test]
+    fn should_slice_token_text_from_the_source(){
+        let compact = tokenize_compact("let é = 1").unwrap();
+        let texts: Vec<&str> = compact.iter().map(|token| token.text(compact.source())).collect();
+        assert_eq!(vec!["let", "é", "=", "1", ""], texts);
+        assert_eq!(4..6, compact.iter().nth(1).unwrap().range());
+        assert!(compact.get(5).is_none());
+    }
+
+    #[test]
+    fn should_return_the_first_error(){
+        assert_eq!(tokenize("a $ b").unwrap_err(), tokenize_compact("a $ b").unwrap_err());
+    }
+}
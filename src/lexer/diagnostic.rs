@@ -0,0 +1,199 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::lexer::{tokenize_all, LexError, Span, Token, TokenType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Suspicious but valid source, lexing is not affected.
+    Warning,
+    Error,
+}
+
+/// Problem found in the source, with an optional note on how to fix it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    pub note: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl From<LexError> for Diagnostic {
+    fn from(err: LexError) -> Self {
+        Diagnostic { severity: Severity::Error, message: err.message, span: err.span, note: None }
+    }
+}
+
+/// `warning: message at line:column`, then the note on its own line.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {} at {}:{}", severity, self.message, self.span.line, self.span.column)?;
+        if let Some(note) = &self.note {
+            write!(f, "\n  note: {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of [`tokenize_with_diagnostics`].
+#[derive(Debug, Clone)]
+pub struct LexOutput<'src> {
+    pub tokens: Vec<Token<'src>>,
+    /// Warnings and errors in source order.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl LexOutput<'_> {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+}
+
+/// Tokenizes the whole source like [`tokenize_all`], reporting errors along
+/// with warnings about valid but suspicious tokens:
+///
+/// - a number with a leading zero, like `012`, which is not octal;
+/// - an identifier mixing ASCII letters with non-ASCII ones that look the same,
+///   like a Cyrillic `р` in `раss`.
+pub fn tokenize_with_diagnostics(source_code: &str) -> LexOutput<'_> {
+    let (tokens, errors) = tokenize_all(source_code);
+    let mut diagnostics: Vec<Diagnostic> = tokens.iter().filter_map(warning).collect();
+    diagnostics.extend(errors.into_iter().map(Diagnostic::from));
+    // stable, a warning and an error at the same place keep that order
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+    LexOutput { tokens, diagnostics }
+}
+
+fn warning(token: &Token<'_>) -> Option<Diagnostic> {
+    let text = token.value();
+    let (message, note) = match token.kind {
+        TokenType::Number if text.len() > 1 && text.starts_with('0') => (
+            format!("number `{}` has a leading zero", text),
+            match text.trim_start_matches('0') {
+                "" => String::from("it is the decimal number 0, numbers are never octal"),
+                digits => format!("it is the decimal number {}, numbers are never octal", digits),
+            },
+        ),
+        TokenType::Identifier => {
+            let lookalike: String = text.chars().map(|c| ascii_lookalike(c).unwrap_or(c)).collect();
+            let confusable = text.chars().find(|&c| ascii_lookalike(c).is_some())?;
+            if !lookalike.is_ascii() {
+                // some other non-ASCII letter already shows it isn't ASCII
+                return None;
+            }
+            (
+                format!("identifier `{}` looks like `{}` but isn't ASCII", text, lookalike),
+                format!("{:?} is U+{:04X}, not {:?}", confusable, confusable as u32, ascii_lookalike(confusable)?),
+            )
+        }
+        _ => return None,
+    };
+    Some(Diagnostic { severity: Severity::Warning, message, span: token.span, note: Some(note) })
+}
+
+// ASCII letter drawn the same as `c` in most fonts, for Cyrillic and Greek
+// letters
+fn ascii_lookalike(c: char) -> Option<char> {
+    let ascii = match c {
+        'а' | 'α' => 'a',
+        'с' => 'c',
+        'е' => 'e',
+        'і' | 'ι' => 'i',
+        'ј' => 'j',
+        'о' | 'ο' => 'o',
+        'р' | 'ρ' => 'p',
+        'ѕ' => 's',
+        'ν' => 'v',
+        'х' => 'x',
+        'у' => 'y',
+        'А' | 'Α' => 'A',
+        'В' | 'Β' => 'B',
+        'С' => 'C',
+        'Е' | 'Ε' => 'E',
+        'Н' | 'Η' => 'H',
+        'І' | 'Ι' => 'I',
+        'К' | 'Κ' => 'K',
+        'М' | 'Μ' => 'M',
+        'Ν' => 'N',
+        'О' | 'Ο' => 'O',
+        'Р' | 'Ρ' => 'P',
+        'Т' | 'Τ' => 'T',
+        'Х' | 'Χ' => 'X',
+        'Υ' => 'Y',
+        'Ζ' => 'Z',
+        _ => return None,
+    };
+    Some(ascii)
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::lexer::diagnostic::{tokenize_with_diagnostics, Severity};
+    use crate::lexer::{tokenize_all, Span, TokenType};
+
+    #[test]
+    fn should_warn_about_leading_zeros(){
+        let output = tokenize_with_diagnostics("let x = 012 + 0 + 00");
+        assert!(!output.has_errors());
+        let found: Vec<(Severity, &str, usize)> =
+            output.diagnostics.iter().map(|d| (d.severity, d.message.as_str(), d.span.start)).collect();
+        let expected = vec![
+            (Severity::Warning, "number `012` has a leading zero", 8),
+            (Severity::Warning, "number `00` has a leading zero", 18),
+        ];
+        assert_eq!(expected, found);
+        assert_eq!(
+            "warning: number `012` has a leading zero at 1:9\n  note: it is the decimal number 12, numbers are never octal",
+            output.diagnostics[0].to_string()
+        );
+        assert_eq!(Some("it is the decimal number 0, numbers are never octal"), output.diagnostics[1].note.as_deref());
+    }
+
+    #[test]
+    fn should_warn_about_identifiers_that_look_ascii(){
+        // Cyrillic `р` and `а`, then Greek `Ο`
+        let output = tokenize_with_diagnostics("let раss = Οk\nlet привет = éa");
+        let messages: Vec<&str> = output.diagnostics.iter().map(|d| d.message.as_str()).collect();
+        let expected = vec![
+            "identifier `раss` looks like `pass` but isn't ASCII",
+            "identifier `Οk` looks like `Ok` but isn't ASCII",
+        ];
+        assert_eq!(expected, messages);
+        assert_eq!(Some("'р' is U+0440, not 'p'"), output.diagnostics[0].note.as_deref());
+        assert_eq!(Span::new(13, 16, 1, 12), output.diagnostics[1].span);
+    }
+
+    #[test]
+    fn should_order_warnings_and_errors_by_span(){
+        let source = "01 @ раss\n\"open\nx $ 007";
+        let output = tokenize_with_diagnostics(source);
+        let found: Vec<(Severity, u32)> = output.diagnostics.iter().map(|d| (d.severity, d.span.line)).collect();
+        let expected = vec![
+            (Severity::Warning, 1),
+            (Severity::Error, 1),
+            (Severity::Warning, 1),
+            (Severity::Error, 2),
+            (Severity::Error, 3),
+            (Severity::Warning, 3),
+        ];
+        assert_eq!(expected, found);
+        assert!(output.diagnostics.windows(2).all(|pair| pair[0].span.start <= pair[1].span.start));
+        assert!(output.has_errors());
+        // the tokens are the same as without diagnostics
+        assert_eq!(tokenize_all(source).0, output.tokens);
+        assert_eq!(Some(TokenType::Eof), output.tokens.last().map(|token| token.kind()));
+    }
+}
@@ -0,0 +1,213 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::lexer::{count_line_breaks, LexError, Lexer, Token, TokenType};
+
+/// A single replacement applied to the source: the bytes in `range` (offsets
+/// into the old source) are replaced with `new_text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub new_text: String,
+}
+
+/// Updates `old_tokens` (a successful tokenization of the old source) after
+/// `edit` turned it into `new_source`.
+///
+/// Tokens before the line containing the edit are kept, lexing restarts at the
+/// beginning of that line and stops as soon as it reaches a token, at the start
+/// of a line past the edit, that lines up with an old one; the remaining old
+/// tokens are reused with shifted spans. The result is always the same as
+/// running `tokenize` on `new_source`.
+pub fn relex<'a>(old_tokens: &[Token<'_>], edit: &TextEdit, new_source: &'a str) -> Result<Vec<Token<'a>>, LexError> {
+    let depths = interpolation_depths(old_tokens);
+    let mut line_start = line_start_of(new_source, edit.range.start);
+    let mut kept = count_before(old_tokens, line_start);
+    // restarting inside an interpolated string would lose the lexer state, so
+    // move the restart point back to a line where no interpolation is open; a
+    // token spanning lines moves it back to the line the token starts on
+    loop {
+        if depths[kept] > 0 {
+            let open = (0..kept).rev().find(|&i| depths[i] == 0).unwrap_or(0);
+            line_start = line_start_of(new_source, old_tokens[open].span.start);
+        } else if old_tokens.get(kept).is_some_and(|t| t.kind != TokenType::Eof && t.span.start < line_start) {
+            line_start = line_start_of(new_source, old_tokens[kept].span.start);
+        } else {
+            break;
+        }
+        kept = count_before(old_tokens, line_start);
+    }
+    let edit_end = edit.range.start + edit.new_text.len();
+
+    let mut tokens: Vec<Token<'a>> = Vec::new();
+    let mut line = 1;
+    for token in &old_tokens[..kept] {
+        tokens.push(rebase(token, new_source, token.span.start, token.span.line));
+        line = token.span.line;
+    }
+    // lines between the start of the last kept token, which may span lines, and
+    // the restart point
+    line += count_line_breaks(&new_source[tokens.last().map_or(0, |t| t.span.start)..line_start]);
+
+    let mut old_index = tokens.len();
+    let mut lexer = Lexer::at_line_start(new_source, line_start, line, tokens.last().map(|t| t.kind));
+    loop {
+        let in_interpolation = lexer.in_interpolation();
+        let Some(result) = lexer.next() else {
+            return Ok(tokens);
+        };
+        let token = result?;
+        if !in_interpolation && token.span.start >= edit_end && count_line_breaks(&new_source[edit_end..token.span.start]) > 0 {
+            // where this token would have started before the edit
+            let old_start = token.span.start - edit_end + edit.range.end;
+            while old_tokens.get(old_index).is_some_and(|t| t.span.start < old_start) {
+                old_index += 1;
+            }
+            let reusable = old_tokens.get(old_index).filter(|old| depths[old_index] == 0 && lines_up(old, &token, old_start));
+            if let Some(old) = reusable {
+                let line_shift = token.span.line as i64 - old.span.line as i64;
+                for old in &old_tokens[old_index..] {
+                    let start = old.span.start - edit.range.end + edit_end;
+                    let line = (old.span.line as i64 + line_shift) as u32;
+                    tokens.push(rebase(old, new_source, start, line));
+                }
+                return Ok(tokens);
+            }
+        }
+        tokens.push(token);
+    }
+}
+
+fn line_start_of(source: &str, offset: usize) -> usize {
+    // an offset between `\r` and `\n` is still on the line they end
+    let end = if source[..offset].ends_with('\r') && source[offset..].starts_with('\n') { offset - 1 } else { offset };
+    source[..end].rfind(['\n', '\r']).map_or(0, |i| i + 1)
+}
+
+// number of leading tokens that end before `offset`, EOF is never counted
+fn count_before(tokens: &[Token<'_>], offset: usize) -> usize {
+    tokens.iter().take_while(|t| t.kind != TokenType::Eof && t.span.end <= offset).count()
+}
+
+// open interpolations right before each token, plus one entry past the end
+fn interpolation_depths(tokens: &[Token<'_>]) -> Vec<u32> {
+    let mut depths = Vec::with_capacity(tokens.len() + 1);
+    let mut depth: u32 = 0;
+    for token in tokens {
+        depths.push(depth);
+        match token.kind {
+            TokenType::StringStart => depth += 1,
+            TokenType::StringEnd => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depths.push(depth);
+    depths
+}
+
+fn lines_up(old: &Token<'_>, new: &Token<'_>, old_start: usize) -> bool {
+    old.span.start == old_start
+        && old.kind == new.kind
+        && old.span.end - old.span.start == new.span.end - new.span.start
+        && old.span.column == new.span.column
+}
+
+// copy of `token` moved to `start` on `line`, with its text taken from the new source
+fn rebase<'a>(token: &Token<'_>, new_source: &'a str, start: usize, line: u32) -> Token<'a> {
+    let mut span = token.span;
+    span.end = start + (span.end - span.start);
+    span.start = start;
+    span.line = line;
+    match token.kind {
+        TokenType::Eof => Token::new(token.value.clone().into_owned(), token.kind, span),
+        kind => Token::from_source_text(&new_source[span.start..span.end], kind, span),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use proptest::prelude::*;
+
+    use crate::lexer::incremental::{relex, TextEdit};
+    use crate::lexer::tokenize;
+
+    fn apply(source: &str, edit: &TextEdit) -> String {
+        format!("{}{}{}", &source[..edit.range.start], edit.new_text, &source[edit.range.end..])
+    }
+
+    fn assert_relex_matches(source: &str, edit: TextEdit) {
+        let new_source = apply(source, &edit);
+        let old_tokens = tokenize(source).unwrap();
+        assert_eq!(tokenize(&new_source), relex(&old_tokens, &edit, &new_source), "{:?} with {:?}", source, edit);
+    }
+
+    #[test]
+    fn should_relex_edit_inside_a_line(){
+        assert_relex_matches("let x = 1\nlet y = 2\nlet z = 3", TextEdit { range: 14..15, new_text: String::from("abc") });
+    }
+
+    #[test]
+    fn should_relex_edit_adding_lines(){
+        assert_relex_matches("let x = 1\nlet y = 2\nlet z = 3", TextEdit { range: 9..9, new_text: String::from("\n\nlet w = 4") });
+    }
+
+    #[test]
+    fn should_relex_edit_removing_lines(){
+        assert_relex_matches("let x = 1\nlet y = 2\nlet z = 3", TextEdit { range: 5..15, new_text: String::new() });
+    }
+
+    #[test]
+    fn should_relex_edit_at_the_end(){
+        assert_relex_matches("let x = 1\n(y)", TextEdit { range: 13..13, new_text: String::from(" + é") });
+    }
+
+    #[test]
+    fn should_relex_edit_inside_multi_line_interpolation(){
+        assert_relex_matches("let s = \"a{\nx\n+ y}b\"\nlet z = 1", TextEdit { range: 15..16, new_text: String::from("zz") });
+        assert_relex_matches("\"a{\nx\n}b\"\n\"c\"", TextEdit { range: 7..10, new_text: String::from(" {") });
+    }
+
+    #[test]
+    fn should_report_errors_introduced_by_edit(){
+        let source = "let x = 1\nlet y = 2";
+        let edit = TextEdit { range: 4..5, new_text: String::from("@") };
+        let new_source = apply(source, &edit);
+        let error = relex(&tokenize(source).unwrap(), &edit, &new_source).unwrap_err();
+        assert_eq!(tokenize(&new_source).unwrap_err(), error);
+    }
+
+    // any sequence of these lexes fine
+    const VALID_PIECES: &[&str] = &[
+        "let", "x", "ab", "é", "45", "0", " ", "  ", "\n", "\r\n", "\r", "(", ")", "+", "-", "x / y", "// c\n", "=", "?", ":",
+        "\"s\"", "\"a{x}b\"", "\"a\\\n  b\"", "`t\nu`", "\"a{\nx + \"c{y}\"\n}b{z}\"",
+    ];
+    // fragments that may leave strings and interpolations open
+    const BROKEN_PIECES: &[&str] = &["`", "\\", "\"", "\"a{", "}b{", "}c\"", "{", "}"];
+
+    fn source_strategy() -> impl Strategy<Value = String> {
+        prop::collection::vec(prop::sample::select(VALID_PIECES), 0..40).prop_map(|pieces| pieces.concat())
+    }
+
+    fn edit_strategy() -> impl Strategy<Value = String> {
+        let pieces: Vec<&str> = VALID_PIECES.iter().chain(BROKEN_PIECES).copied().collect();
+        prop::collection::vec(prop::sample::select(pieces), 0..10).prop_map(|pieces| pieces.concat())
+    }
+
+    proptest! {
+        #[test]
+        fn should_match_full_tokenize_for_random_edits(
+            source in source_strategy(),
+            new_text in edit_strategy(),
+            a in any::<prop::sample::Index>(),
+            b in any::<prop::sample::Index>(),
+        ) {
+            let boundaries: Vec<usize> = source.char_indices().map(|(i, _)| i).chain([source.len()]).collect();
+            let (a, b) = (*a.get(&boundaries), *b.get(&boundaries));
+            let edit = TextEdit { range: a.min(b)..a.max(b), new_text };
+            let new_source = apply(&source, &edit);
+            let old_tokens = tokenize(&source).unwrap();
+            prop_assert_eq!(tokenize(&new_source), relex(&old_tokens, &edit, &new_source));
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::lexer::{LexError, Lexer, Token, TokenType};
+
+/// Handle for a string stored in an [`Interner`]. Two symbols from the same
+/// interner are equal exactly when their strings are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+/// Stores each distinct string once and hands out a [`Symbol`] for it.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    symbols: BTreeMap<Box<str>, Symbol>,
+    strings: Vec<Box<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// The symbol of `text`, interning it the first time it is seen.
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(text) {
+            return symbol;
+        }
+        let symbol = Symbol(self.strings.len() as u32);
+        self.strings.push(Box::from(text));
+        self.symbols.insert(Box::from(text), symbol);
+        symbol
+    }
+
+    /// The string `symbol` was interned from. Panics if `symbol` comes from
+    /// another interner holding more strings.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.strings[symbol.0 as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+/// Lexes any number of sources with one shared [`Interner`], so identifier and
+/// keyword tokens carry a [`Token::symbol`] and compare in O(1).
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    interner: Interner,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    /// Like [`tokenize`](crate::lexer::tokenize), with symbols filled in.
+    pub fn tokenize<'a>(&mut self, source: &'a str) -> Result<Vec<Token<'a>>, LexError> {
+        self.lex(Lexer::new(source))
+    }
+
+    /// Runs `lexer` to the end, interning identifiers and keywords on the way.
+    pub fn lex<'a>(&mut self, lexer: Lexer<'a>) -> Result<Vec<Token<'a>>, LexError> {
+        lexer
+            .map(|result| {
+                result.map(|mut token| {
+                    if token.kind == TokenType::Identifier || token.kind.is_keyword() {
+                        token.symbol = Some(self.interner.intern(&token.value));
+                    }
+                    token
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::lexer::intern::{Interner, Session};
+    use crate::lexer::{tokenize, TokenType};
+
+    #[test]
+    fn should_return_the_same_symbol_for_the_same_string(){
+        let mut interner = Interner::new();
+        let first = interner.intern("count");
+        assert_eq!(first, interner.intern("count"));
+        assert_eq!(1, interner.len());
+        assert_eq!("count", interner.resolve(first));
+    }
+
+    #[test]
+    fn should_return_different_symbols_for_different_strings(){
+        let mut interner = Interner::new();
+        let a = interner.intern("a");
+        let b = interner.intern("b");
+        assert_ne!(a, b);
+        assert_eq!("a", interner.resolve(a));
+        assert_eq!("b", interner.resolve(b));
+    }
+
+    #[test]
+    fn should_share_one_symbol_between_repeated_identifiers(){
+        let mut session = Session::new();
+        let tokens = session.tokenize("let total = total + x\nlet x = total").unwrap();
+        let symbol_of = |i: usize| tokens[i].symbol().unwrap();
+        assert_eq!(symbol_of(1), symbol_of(3));
+        assert_eq!(symbol_of(1), symbol_of(9));
+        assert_eq!(symbol_of(5), symbol_of(7));
+        assert_ne!(symbol_of(1), symbol_of(5));
+        // keywords are interned too
+        assert_eq!(symbol_of(0), symbol_of(6));
+        assert_eq!("total", session.interner().resolve(symbol_of(1)));
+        assert_eq!(None, tokens[2].symbol());
+        assert_eq!(TokenType::Eof, tokens[10].kind());
+        assert_eq!(None, tokens[10].symbol());
+    }
+
+    #[test]
+    fn should_keep_symbols_across_sources_of_one_session(){
+        let mut session = Session::new();
+        let first = session.tokenize("x").unwrap();
+        let second = session.tokenize("y x").unwrap();
+        assert_eq!(first[0].symbol(), second[1].symbol());
+        assert_eq!(2, session.interner().len());
+    }
+
+    #[test]
+    fn should_compare_interned_tokens_like_plain_ones(){
+        let source = "let a = a";
+        let interned = Session::new().tokenize(source).unwrap();
+        let plain = tokenize(source).unwrap();
+        assert_eq!(plain, interned);
+        assert_ne!(interned[1], interned[3]);
+        assert!(interned[1].same_kind_and_text(&interned[3]));
+    }
+}
@@ -1 +1,1919 @@
-pub mod lexer;
\ No newline at end of file
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FusedIterator;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+pub mod compact;
+pub mod diagnostic;
+pub mod incremental;
+pub mod intern;
+pub mod source;
+pub mod stats;
+pub mod stream;
+pub mod token;
+
+pub use token::{keyword_kind, keywords, tokens_eq_ignoring_spans, tokens_on_line, Span, Token, TokenType, Trivia, TriviaKind, KEYWORDS};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErrorKind {
+    UnrecognizedCharacter(char),
+    // a string literal or interpolation still open at the end of its line or
+    // of the input
+    UnterminatedString,
+    // a regex literal without its closing `/` on the same line
+    UnterminatedRegex,
+    InvalidUtf8,
+    // offsets of the source don't fit the compact token representation
+    SourceTooLarge,
+    // the source could not be read at all
+    #[cfg(feature = "std")]
+    Io(io::ErrorKind),
+}
+
+/// Error produced when the source contains something the lexer cannot handle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+    pub message: String,
+    /// Name of the file the source came from, if it was read from one.
+    pub file: Option<String>,
+}
+
+impl LexError {
+    fn unrecognized_character(character: char, span: Span) -> Self {
+        LexError {
+            kind: LexErrorKind::UnrecognizedCharacter(character),
+            span,
+            message: format!("unrecognized character {:?}", character),
+            file: None,
+        }
+    }
+
+    fn unterminated_string(span: Span) -> Self {
+        LexError {
+            kind: LexErrorKind::UnterminatedString,
+            span,
+            message: String::from("unterminated string"),
+            file: None,
+        }
+    }
+
+    fn unterminated_regex(span: Span) -> Self {
+        LexError {
+            kind: LexErrorKind::UnterminatedRegex,
+            span,
+            message: String::from("unterminated regex"),
+            file: None,
+        }
+    }
+
+    fn source_too_large() -> Self {
+        LexError {
+            kind: LexErrorKind::SourceTooLarge,
+            span: Position::start().span_to(Position::start()),
+            message: String::from("source is too large, the limit is 4 GiB"),
+            file: None,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn invalid_utf8(span: Span) -> Self {
+        LexError {
+            kind: LexErrorKind::InvalidUtf8,
+            span,
+            message: String::from("invalid UTF-8"),
+            file: None,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn io(err: &io::Error) -> Self {
+        LexError {
+            kind: LexErrorKind::Io(err.kind()),
+            span: Position::start().span_to(Position::start()),
+            message: err.to_string(),
+            file: None,
+        }
+    }
+
+    /// Records the file the source came from, for errors found after it was
+    /// read.
+    #[cfg(feature = "std")]
+    pub fn in_file(mut self, path: &Path) -> Self {
+        self.file = Some(path.display().to_string());
+        self
+    }
+
+    // IO errors happen before lexing starts, and a source that is too large is
+    // rejected before it, so their span means nothing
+    fn has_position(&self) -> bool {
+        if self.kind == LexErrorKind::SourceTooLarge {
+            return false;
+        }
+        #[cfg(feature = "std")]
+        if let LexErrorKind::Io(_) = self.kind {
+            return false;
+        }
+        true
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.has_position()) {
+            (Some(file), false) => write!(f, "{}: {}", file, self.message),
+            (None, false) => write!(f, "{}", self.message),
+            (Some(file), true) => write!(f, "{}:{}:{}: {}", file, self.span.line, self.span.column, self.message),
+            (None, true) => write!(f, "{} at {}:{}", self.message, self.span.line, self.span.column),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LexError {}
+
+/// What one step of a span column stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnUnit {
+    /// Every byte of the UTF-8 encoding is a column.
+    Bytes,
+    /// Every character is a column.
+    #[default]
+    Chars,
+    /// Every UTF-16 code unit is a column, matching LSP positions.
+    Utf16CodeUnits,
+}
+
+/// Controls how the lexer computes span lines and columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LexerConfig {
+    /// A tab moves the column to the next multiple of `tab_width` (plus one,
+    /// columns are 1-based). `1` counts a tab like any other character.
+    pub tab_width: u8,
+    pub column_unit: ColumnUnit,
+    /// Whether the lexer ends with an [`TokenType::Eof`] token. Turning it off
+    /// is handy when token lists from several sources are concatenated.
+    pub emit_eof: bool,
+    /// Whether spans get a line and column. Without them spans only hold byte
+    /// offsets and their line and column are 0, a
+    /// [`SourceFile`](crate::lexer::source::SourceFile) works them out when they
+    /// are needed, see [`SourceFile::tokenize_offsets`](crate::lexer::source::SourceFile::tokenize_offsets).
+    pub positions: bool,
+}
+
+impl Default for LexerConfig {
+    fn default() -> Self {
+        LexerConfig { tab_width: 1, column_unit: ColumnUnit::Chars, emit_eof: true, positions: true }
+    }
+}
+
+impl LexerConfig {
+    // number of columns `c` takes when it starts at `column`
+    fn width(&self, c: char, column: u32) -> u32 {
+        if c == '\t' {
+            let tab_width = u32::from(self.tab_width.max(1));
+            return tab_width - (column - 1) % tab_width;
+        }
+        match self.column_unit {
+            ColumnUnit::Bytes => c.len_utf8() as u32,
+            ColumnUnit::Chars => 1,
+            ColumnUnit::Utf16CodeUnits => c.len_utf16() as u32,
+        }
+    }
+}
+
+// `\n`, `\r` and `\r\n` all end a line
+pub(crate) fn is_line_break(c: char) -> bool {
+    c == '\n' || c == '\r'
+}
+
+// number of line endings in `text`, `\r\n` counts once
+pub(crate) fn count_line_breaks(text: &str) -> u32 {
+    (text.matches(is_line_break).count() - text.matches("\r\n").count()) as u32
+}
+
+// `text` of a string token with every `\` before a line break removed along
+// with the break and the whitespace after it; other escapes are left as they are
+pub(crate) fn without_line_continuations(text: &str) -> String {
+    let mut value = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek().copied()) {
+            ('\\', Some(next)) if is_line_break(next) => {
+                chars.next();
+                if next == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                while chars.next_if(|&c| c.is_whitespace() && !is_line_break(c)).is_some() {}
+            }
+            ('\\', Some(next)) => {
+                chars.next();
+                value.push(c);
+                value.push(next);
+            }
+            _ => value.push(c),
+        }
+    }
+    value
+}
+
+// kinds that end an operand, so a `/` after them divides
+fn ends_value(kind: TokenType) -> bool {
+    matches!(
+        kind,
+        TokenType::Null
+            | TokenType::Infinity
+            | TokenType::NaN
+            | TokenType::Number
+            | TokenType::Identifier
+            | TokenType::StringLiteral
+            | TokenType::TemplateLiteral
+            | TokenType::RegexLiteral
+            | TokenType::StringEnd
+            | TokenType::CloseParen
+            | TokenType::CloseBracket
+            | TokenType::PlusPlus
+            | TokenType::MinusMinus
+    )
+}
+
+// value of a token with the given kind and source text, only continued strings
+// don't use the text as it is
+pub(crate) fn token_value(kind: TokenType, text: &str) -> Cow<'_, str> {
+    let is_string = matches!(
+        kind,
+        TokenType::StringLiteral | TokenType::StringStart | TokenType::StringMiddle | TokenType::StringEnd
+    );
+    if is_string && (text.contains("\\\n") || text.contains("\\\r")) {
+        Cow::Owned(without_line_continuations(text))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+// current scanning position, updated for every consumed character
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    offset: usize,
+    line: u32,
+    column: u32,
+    // the last character was `\r`, so a `\n` right after it ends no new line
+    after_cr: bool,
+}
+
+impl Position {
+    fn start() -> Self {
+        Position { offset: 0, line: 1, column: 1, after_cr: false }
+    }
+
+    fn advance(&mut self, c: char, config: &LexerConfig) {
+        self.offset += c.len_utf8();
+        if !config.positions {
+            return;
+        }
+        match c {
+            '\n' if self.after_cr => {}
+            '\n' | '\r' => {
+                self.line += 1;
+                self.column = 1;
+            }
+            _ => self.column += config.width(c, self.column),
+        }
+        self.after_cr = c == '\r';
+    }
+
+    // span from this position up to `end`
+    fn span_to(&self, end: Position) -> Span {
+        Span::new(self.offset, end.offset, self.line, self.column)
+    }
+}
+
+/// Saved state of a [`Lexer`], see [`Lexer::checkpoint`].
+#[derive(Debug, Clone, Copy)]
+pub struct Checkpoint {
+    pos: Position,
+    interpolations: u32,
+    previous: Option<TokenType>,
+    finished: bool,
+}
+
+/// Lazy tokenizer producing one token at a time.
+///
+/// The iterator yields every token including the final EOF and then stops. It
+/// also stops right after yielding an error, so it never produces anything past
+/// the first problem in the source.
+pub struct Lexer<'a> {
+    source: &'a str,
+    // the cursor is `pos.offset`, always on a char boundary of `source`
+    pos: Position,
+    preserve_trivia: bool,
+    // unrecognized characters become `Error` tokens instead of errors
+    tolerant: bool,
+    config: LexerConfig,
+    // number of string interpolations currently open, a `}` closes the
+    // innermost one and continues its string
+    interpolations: u32,
+    // kind of the last token, a `/` right after a value is a division and
+    // anywhere else starts a regex
+    previous: Option<TokenType>,
+    finished: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source_code: &'a str) -> Self {
+        Lexer {
+            source: source_code,
+            pos: Position::start(),
+            preserve_trivia: false,
+            tolerant: false,
+            config: LexerConfig::default(),
+            interpolations: 0,
+            previous: None,
+            finished: false,
+        }
+    }
+
+    /// Keeps whitespace and comments as trivia attached to the tokens instead
+    /// of dropping them.
+    pub fn with_trivia(mut self) -> Self {
+        self.preserve_trivia = true;
+        self
+    }
+
+    /// Keeps lexing past unrecognized characters: every run of them becomes a
+    /// single [`TokenType::Error`] token instead of a [`LexError`]. Other
+    /// errors, like unterminated strings, are still returned.
+    pub fn tolerant(mut self) -> Self {
+        self.tolerant = true;
+        self
+    }
+
+    /// Computes span columns according to `config` instead of counting every
+    /// character, tabs included, as one column.
+    pub fn with_config(mut self, config: LexerConfig) -> Self {
+        self.config = config;
+        if !config.positions {
+            (self.pos.line, self.pos.column) = (0, 0);
+        }
+        self
+    }
+
+    /// Saves the current state so [`Lexer::rewind`] can come back to it, e.g. to
+    /// retry a speculative parse. Cheap, nothing is copied but a few integers.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            pos: self.pos,
+            interpolations: self.interpolations,
+            previous: self.previous,
+            finished: self.finished,
+        }
+    }
+
+    /// Goes back to a state saved by [`Lexer::checkpoint`] on this lexer: the
+    /// tokens, and errors, produced since then are produced again. A checkpoint
+    /// taken after an error or EOF keeps the lexer finished.
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        debug_assert!(checkpoint.pos.offset <= self.source.len(), "checkpoint from another lexer");
+        self.pos = checkpoint.pos;
+        self.interpolations = checkpoint.interpolations;
+        self.previous = checkpoint.previous;
+        self.finished = checkpoint.finished;
+    }
+
+    // lexer that starts scanning at `offset`, which must be the first byte of
+    // line number `line`, right after a token of kind `previous`
+    pub(crate) fn at_line_start(source_code: &'a str, offset: usize, line: u32, previous: Option<TokenType>) -> Self {
+        let mut lexer = Lexer::new(source_code);
+        lexer.pos = Position { offset, line, column: 1, after_cr: false };
+        lexer.previous = previous;
+        lexer
+    }
+
+    // scans the next token, whitespace and comments are skipped (or recorded as
+    // trivia); after the end of input this keeps returning EOF tokens
+    fn next_token(&mut self) -> Result<Token<'a>, LexError> {
+        let leading_trivia = self.trivia(false);
+        let start = self.pos;
+        let mut token = match self.scan_token() {
+            Err(err) if self.tolerant && matches!(err.kind, LexErrorKind::UnrecognizedCharacter(_)) => {
+                self.error_token(start)
+            }
+            result => result?,
+        };
+        self.previous = Some(token.kind);
+        if self.preserve_trivia {
+            token.leading_trivia = leading_trivia;
+            if token.kind != TokenType::Eof {
+                token.trailing_trivia = self.trivia(true);
+            }
+        }
+        Ok(token)
+    }
+
+    fn scan_token(&mut self) -> Result<Token<'a>, LexError> {
+        let start = self.pos;
+        let Some(current) = self.peek() else {
+            if self.interpolations > 0 {
+                self.interpolations = 0;
+                return Err(LexError::unterminated_string(start.span_to(self.pos)));
+            }
+            // EOF is zero-width and sits right after the last character
+            return Ok(Token::new("", TokenType::Eof, self.pos.span_to(self.pos)));
+        };
+        match current {
+            '(' => Ok(self.single(TokenType::OpenParen)),
+            ')' => Ok(self.single(TokenType::CloseParen)),
+            '[' => Ok(self.single(TokenType::OpenBracket)),
+            ']' => Ok(self.single(TokenType::CloseBracket)),
+            // braces aren't tracked inside an interpolation, where a `}` always
+            // continues the string, so a `{` there is unrecognized
+            '{' if self.interpolations == 0 => Ok(self.single(TokenType::OpenBrace)),
+            ',' => Ok(self.single(TokenType::Comma)),
+            '+' if self.peek_second() == Some('+') => Ok(self.double(TokenType::PlusPlus)),
+            '-' if self.peek_second() == Some('-') => Ok(self.double(TokenType::MinusMinus)),
+            '/' if self.at_doc_comment() => {
+                let text = self.take_while(|c| !is_line_break(c));
+                Ok(Token::new(text, TokenType::DocComment, start.span_to(self.pos)))
+            },
+            '/' if !self.previous.is_some_and(ends_value) => self.regex(),
+            '*' if self.peek_second() == Some('*') => Ok(self.double(TokenType::BinaryOperator)),
+            '=' if self.peek_second() == Some('=') => Ok(self.double(TokenType::BinaryOperator)),
+            '+' | '-' | '*' | '/' | '%' => Ok(self.single(TokenType::BinaryOperator)),
+            '=' => Ok(self.single(TokenType::Equals)),
+            '!' => Ok(self.single(TokenType::Bang)),
+            '?' if self.peek_second() == Some('.') => Ok(self.double(TokenType::SafeNavigation)),
+            '?' if self.peek_second() == Some('?') => Ok(self.double(TokenType::BinaryOperator)),
+            '?' => Ok(self.single(TokenType::QuestionMark)),
+            ':' if self.peek_second() == Some(':') => Ok(self.double(TokenType::PathSeparator)),
+            ':' => Ok(self.single(TokenType::Colon)),
+            '.' if self.source[self.pos.offset..].starts_with("...") => Ok(self.multi(3, TokenType::Ellipsis)),
+            '#' => Ok(self.single(TokenType::Hash)),
+            '"' => self.string(false),
+            '`' => self.template(),
+            '}' if self.interpolations > 0 => {
+                self.interpolations -= 1;
+                self.string(true)
+            },
+            '}' => Ok(self.single(TokenType::CloseBrace)),
+            // build number token
+            c if c.is_numeric() => {
+                let num = self.take_while(|c| c.is_numeric());
+                Ok(Token::new(num, TokenType::Number, start.span_to(self.pos)))
+            },
+            // build letters token
+            c if c.is_alphabetic() => {
+                let ident = self.take_while(|c| c.is_alphabetic());
+
+                let token_kind = keyword_kind(ident).unwrap_or(TokenType::Identifier);
+
+                Ok(Token::new(ident, token_kind, start.span_to(self.pos)))
+            },
+            c => {
+                self.bump();
+                Err(LexError::unrecognized_character(c, start.span_to(self.pos)))
+            }
+        }
+    }
+
+    // `Error` token for the unrecognized character before the current position
+    // and every unrecognized character right after it
+    fn error_token(&mut self, start: Position) -> Token<'a> {
+        // whitespace is never part of the run, but `scan_token` doesn't skip it
+        while self.peek().is_some_and(|c| !c.is_whitespace()) {
+            let (pos, interpolations) = (self.pos, self.interpolations);
+            match self.scan_token() {
+                Err(err) if matches!(err.kind, LexErrorKind::UnrecognizedCharacter(_)) => {}
+                _ => {
+                    // not part of the run, it is scanned again as the next token
+                    self.pos = pos;
+                    self.interpolations = interpolations;
+                    break;
+                }
+            }
+        }
+        Token::new(self.slice_from(start), TokenType::Error, start.span_to(self.pos))
+    }
+
+    // string literal or part of an interpolated string, starting at the opening
+    // `"` or at the `}` closing an interpolation; the token keeps the raw text,
+    // except that line continuations are removed from it
+    fn string(&mut self, after_interpolation: bool) -> Result<Token<'a>, LexError> {
+        let start = self.pos;
+        let mut continued = false;
+        self.bump();
+        let kind = loop {
+            match self.peek() {
+                Some('"') => {
+                    self.bump();
+                    break if after_interpolation { TokenType::StringEnd } else { TokenType::StringLiteral };
+                }
+                Some('{') => {
+                    self.bump();
+                    self.interpolations += 1;
+                    break if after_interpolation { TokenType::StringMiddle } else { TokenType::StringStart };
+                }
+                // escaped characters, including `\"` and `\{`, never end the string;
+                // `\` right before a line break continues the string on the next
+                // line, without the break and the indentation after it
+                Some('\\') => {
+                    self.bump();
+                    match self.peek() {
+                        Some(c) if is_line_break(c) => {
+                            if self.bump() == Some('\r') && self.peek() == Some('\n') {
+                                self.bump();
+                            }
+                            self.take_while(|c| c.is_whitespace() && !is_line_break(c));
+                            continued = true;
+                        }
+                        Some(_) => {
+                            self.bump();
+                        }
+                        None => {}
+                    }
+                }
+                Some('\n' | '\r') | None => return Err(LexError::unterminated_string(start.span_to(self.pos))),
+                Some(_) => {
+                    self.bump();
+                }
+            }
+        };
+        let text = self.slice_from(start);
+        let span = start.span_to(self.pos);
+        if continued {
+            Ok(Token { raw: Some(Box::from(text)), ..Token::new(without_line_continuations(text), kind, span) })
+        } else {
+            Ok(Token::new(text, kind, span))
+        }
+    }
+
+    // `/pattern/flags`, the token keeps the raw text; a `/` inside `[...]` or
+    // after a `\\` doesn't end the pattern
+    fn regex(&mut self) -> Result<Token<'a>, LexError> {
+        let start = self.pos;
+        self.bump();
+        let mut in_class = false;
+        loop {
+            match self.peek() {
+                Some('/') if !in_class => break,
+                Some('\\') => {
+                    self.bump();
+                    if self.peek().is_some_and(|c| !is_line_break(c)) {
+                        self.bump();
+                    }
+                }
+                Some(c) if !is_line_break(c) => {
+                    in_class = match c {
+                        '[' => true,
+                        ']' => false,
+                        _ => in_class,
+                    };
+                    self.bump();
+                }
+                _ => return Err(LexError::unterminated_regex(start.span_to(self.pos))),
+            }
+        }
+        self.bump();
+        self.take_while(|c| c.is_alphabetic());
+        Ok(Token::new(self.slice_from(start), TokenType::RegexLiteral, start.span_to(self.pos)))
+    }
+
+    // backtick string, which may span lines; the token keeps the raw text
+    // including the line breaks
+    fn template(&mut self) -> Result<Token<'a>, LexError> {
+        let start = self.pos;
+        self.bump();
+        loop {
+            match self.bump() {
+                Some('`') => break,
+                // `\`` doesn't end the template
+                Some('\\') => {
+                    self.bump();
+                }
+                Some(_) => {}
+                None => return Err(LexError::unterminated_string(start.span_to(self.pos))),
+            }
+        }
+        Ok(Token::new(self.slice_from(start), TokenType::TemplateLiteral, start.span_to(self.pos)))
+    }
+
+    // `///` starts a doc comment, but `////` and longer are plain comments
+    fn at_doc_comment(&self) -> bool {
+        let rest = &self.source[self.pos.offset..];
+        rest.starts_with("///") && !rest.starts_with("////")
+    }
+
+    // true while the lexer is inside the `{...}` of an interpolated string
+    pub(crate) fn in_interpolation(&self) -> bool {
+        self.interpolations > 0
+    }
+
+    // consumes whitespace and comments, they are only kept when preserving
+    // trivia; trailing trivia stops right after the first newline
+    fn trivia(&mut self, trailing: bool) -> Vec<Trivia<'a>> {
+        let mut trivia = Vec::new();
+        while let Some(current) = self.peek() {
+            let start = self.pos;
+            let kind = match current {
+                '\r' if self.peek_second() == Some('\n') => {
+                    self.bump();
+                    self.bump();
+                    TriviaKind::Newline
+                }
+                '\n' | '\r' => {
+                    self.bump();
+                    TriviaKind::Newline
+                }
+                c if c.is_whitespace() => {
+                    self.take_while(|c| c.is_whitespace() && !is_line_break(c));
+                    TriviaKind::Whitespace
+                }
+                '/' if self.peek_second() == Some('/') && !self.at_doc_comment() => {
+                    self.take_while(|c| !is_line_break(c));
+                    TriviaKind::LineComment
+                }
+                _ => break,
+            };
+            if self.preserve_trivia {
+                trivia.push(Trivia::new(self.slice_from(start), kind, start.span_to(self.pos)));
+            }
+            if trailing && kind == TriviaKind::Newline {
+                break;
+            }
+        }
+        trivia
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos.offset..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.source[self.pos.offset..].chars().nth(1)
+    }
+
+    // consume the next character, keeping the position in sync
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos.advance(c, &self.config);
+        Some(c)
+    }
+
+    // token made of the current character only
+    fn single(&mut self, kind: TokenType) -> Token<'a> {
+        let start = self.pos;
+        self.bump();
+        Token::new(self.slice_from(start), kind, start.span_to(self.pos))
+    }
+
+    // token made of the current and the next character
+    fn double(&mut self, kind: TokenType) -> Token<'a> {
+        self.multi(2, kind)
+    }
+
+    // token made of the next `len` characters
+    fn multi(&mut self, len: usize, kind: TokenType) -> Token<'a> {
+        let start = self.pos;
+        for _ in 0..len {
+            self.bump();
+        }
+        Token::new(self.slice_from(start), kind, start.span_to(self.pos))
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.bump();
+        }
+        self.slice_from(start)
+    }
+
+    // source text between `start` and the current position
+    fn slice_from(&self, start: Position) -> &'a str {
+        &self.source[start.offset..self.pos.offset]
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.next_token();
+        self.finished = !matches!(&result, Ok(token) if token.kind != TokenType::Eof);
+        if !self.config.emit_eof && matches!(&result, Ok(token) if token.kind == TokenType::Eof) {
+            return None;
+        }
+        Some(result)
+    }
+}
+
+impl FusedIterator for Lexer<'_> {}
+
+pub fn tokenize(source_code: &str) -> Result<Vec<Token<'_>>, LexError> {
+    Lexer::new(source_code).collect()
+}
+
+/// Tokens of `source_code` produced one at a time as the iterator is advanced,
+/// see [`Lexer`]. Collecting it gives the same result as [`tokenize`].
+pub fn iter_tokens(source_code: &str) -> impl Iterator<Item = Result<Token<'_>, LexError>> + '_ {
+    Lexer::new(source_code)
+}
+
+/// Like [`tokenize`], but unrecognized characters come back as
+/// [`TokenType::Error`] tokens, see [`Lexer::tolerant`].
+pub fn tokenize_tolerant(source_code: &str) -> Result<Vec<Token<'_>>, LexError> {
+    Lexer::new(source_code).tolerant().collect()
+}
+
+/// Like [`tokenize`], but every token carries the whitespace and comments
+/// around it, so the source can be rebuilt exactly from the tokens with
+/// [`reconstruct`].
+pub fn tokenize_preserving_trivia(source_code: &str) -> Result<Vec<Token<'_>>, LexError> {
+    Lexer::new(source_code).with_trivia().collect()
+}
+
+/// Source text of `tokens`: the raw text of every token with the trivia around
+/// it. For tokens from [`tokenize_preserving_trivia`] this is exactly the
+/// source they were lexed from.
+pub fn reconstruct(tokens: &[Token<'_>]) -> String {
+    let mut source = String::new();
+    for token in tokens {
+        token.leading_trivia.iter().for_each(|t| source.push_str(&t.text));
+        source.push_str(token.raw_text());
+        token.trailing_trivia.iter().for_each(|t| source.push_str(&t.text));
+    }
+    source
+}
+
+/// Reads the whole input as source text, checking that it is valid UTF-8.
+#[cfg(feature = "std")]
+pub fn read_source<R: Read>(mut reader: R) -> Result<String, LexError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(|err| LexError::io(&err))?;
+
+    String::from_utf8(bytes).map_err(|err| {
+        let error = err.utf8_error();
+        let valid = &err.as_bytes()[..error.valid_up_to()];
+        // the prefix is valid, so positions can be counted on it
+        let mut pos = Position::start();
+        for c in core::str::from_utf8(valid).unwrap_or_default().chars() {
+            pos.advance(c, &LexerConfig::default());
+        }
+        let mut end = pos;
+        end.offset += error.error_len().unwrap_or(err.as_bytes().len() - valid.len());
+        LexError::invalid_utf8(pos.span_to(end))
+    })
+}
+
+/// Reads a file as source text, errors record the file name.
+#[cfg(feature = "std")]
+pub fn read_file(path: &Path) -> Result<String, LexError> {
+    let file = File::open(path).map_err(|err| LexError::io(&err).in_file(path))?;
+    read_source(file).map_err(|err| err.in_file(path))
+}
+
+/// Reads the whole input and tokenizes it. Since the text is read into a
+/// buffer owned by this function, the returned tokens own their text.
+#[cfg(feature = "std")]
+pub fn tokenize_reader<R: Read>(reader: R) -> Result<Vec<Token<'static>>, LexError> {
+    let source_code = read_source(reader)?;
+    let tokens = tokenize(&source_code)?;
+    Ok(tokens.iter().map(Token::to_owned).collect())
+}
+
+/// Reads and tokenizes a file, errors record the file name.
+#[cfg(feature = "std")]
+pub fn tokenize_file(path: &Path) -> Result<Vec<Token<'static>>, LexError> {
+    let source_code = read_file(path)?;
+    let tokens = tokenize(&source_code).map_err(|err| err.in_file(path))?;
+    Ok(tokens.iter().map(Token::to_owned).collect())
+}
+
+/// Tokenizes the whole source, recording an error for every problem instead of
+/// stopping at the first one. Bad characters are skipped, so the returned tokens
+/// are a best effort for whatever could be recognized.
+pub fn tokenize_all(source_code: &str) -> (Vec<Token<'_>>, Vec<LexError>) {
+    let mut lexer = Lexer::new(source_code);
+    let mut tokens:Vec<Token> = Vec::new();
+    let mut errors:Vec<LexError> = Vec::new();
+
+    loop {
+        match lexer.next_token() {
+            Ok(token) if token.kind == TokenType::Eof => {
+                tokens.push(token);
+                break;
+            }
+            Ok(token) => tokens.push(token),
+            Err(err) => errors.push(err),
+        }
+    }
+    (tokens, errors)
+}
+
+#[cfg(test)]
+mod tests{
+    use std::borrow::Cow;
+
+    use proptest::prelude::*;
+
+    use crate::lexer::{
+        keywords, reconstruct, tokenize, tokenize_all, tokenize_preserving_trivia, tokenize_tolerant, tokens_eq_ignoring_spans,
+        ColumnUnit, LexError,
+        LexErrorKind, Lexer, LexerConfig, Span, Token, TokenType, Trivia, TriviaKind,
+    };
+
+    // expected token, spans are ignored by `tokens_eq_ignoring_spans`
+    fn tok(kind: TokenType, value: &'static str) -> Token<'static> {
+        Token::new(value, kind, Span::new(0, 0, 0, 0))
+    }
+
+    fn eof() -> Token<'static> {
+        tok(TokenType::Eof, "")
+    }
+
+    // kinds of the tokens of `source` without the final EOF; unrecognized
+    // characters show up as `Error` tokens
+    fn lex_kinds(source: &str) -> Vec<TokenType> {
+        let tokens = tokenize_tolerant(source).unwrap();
+        tokens.iter().map(|t| t.kind).filter(|&kind| kind != TokenType::Eof).collect()
+    }
+
+    #[test]
+    fn should_analyze_simple_code() {
+        let source = "let x = 45 * (4 / 3)";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Let, "let"),
+            tok(TokenType::Identifier, "x"),
+            tok(TokenType::Equals, "="),
+            tok(TokenType::Number, "45"),
+            tok(TokenType::BinaryOperator, "*"),
+            tok(TokenType::OpenParen, "("),
+            tok(TokenType::Number, "4"),
+            tok(TokenType::BinaryOperator, "/"),
+            tok(TokenType::Number, "3"),
+            tok(TokenType::CloseParen, ")"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_analyze_some_keywords(){
+        let source = "null let";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![tok(TokenType::Null, "null"), tok(TokenType::Let, "let"), eof()];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_analyze_int_numbers(){
+        let source = "0 2 9 4567890";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Number, "0"),
+            tok(TokenType::Number, "2"),
+            tok(TokenType::Number, "9"),
+            tok(TokenType::Number, "4567890"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_analyze_variable_names(){
+        let source = "x y z someVariable VARIABLE";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Identifier, "x"),
+            tok(TokenType::Identifier, "y"),
+            tok(TokenType::Identifier, "z"),
+            tok(TokenType::Identifier, "someVariable"),
+            tok(TokenType::Identifier, "VARIABLE"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_ignore_whitespaces(){
+        let source = "\n\t  \r\n";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![eof()];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should only contain EOF", tokens);
+    }
+
+    #[test]
+    fn should_analyze_some_operators(){
+        // a `/` with no operand before it would start a regex
+        let source = "+ - * x / %";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::BinaryOperator, "+"),
+            tok(TokenType::BinaryOperator, "-"),
+            tok(TokenType::BinaryOperator, "*"),
+            tok(TokenType::Identifier, "x"),
+            tok(TokenType::BinaryOperator, "/"),
+            tok(TokenType::BinaryOperator, "%"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_keep_number_token_for_single_number(){
+        let tokens = tokenize("42").unwrap();
+        let expected = vec![
+            Token::new("42", TokenType::Number, Span::new(0, 2, 1, 1)),
+            Token::new("", TokenType::Eof, Span::new(2, 2, 1, 3)),
+        ];
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn should_attach_spans_to_tokens(){
+        let source = "let x = 45\n(x)";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            (TokenType::Let, Span::new(0, 3, 1, 1)),
+            (TokenType::Identifier, Span::new(4, 5, 1, 5)),
+            (TokenType::Equals, Span::new(6, 7, 1, 7)),
+            (TokenType::Number, Span::new(8, 10, 1, 9)),
+            (TokenType::OpenParen, Span::new(11, 12, 2, 1)),
+            (TokenType::Identifier, Span::new(12, 13, 2, 2)),
+            (TokenType::CloseParen, Span::new(13, 14, 2, 3)),
+            (TokenType::Eof, Span::new(14, 14, 2, 4)),
+        ];
+        let actual: Vec<(TokenType, Span)> = tokens.iter().map(|t| (t.kind, t.span)).collect();
+        assert_eq!(expected, actual, "{:?} has unexpected spans", tokens);
+    }
+
+    #[test]
+    fn should_count_bytes_and_chars_separately_in_spans(){
+        // `é` takes two bytes but only one column
+        let source = "é 7";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            (TokenType::Identifier, Span::new(0, 2, 1, 1)),
+            (TokenType::Number, Span::new(3, 4, 1, 3)),
+            (TokenType::Eof, Span::new(4, 4, 1, 4)),
+        ];
+        let actual: Vec<(TokenType, Span)> = tokens.iter().map(|t| (t.kind, t.span)).collect();
+        assert_eq!(expected, actual, "{:?} has unexpected spans", tokens);
+        assert_eq!("7", &source[tokens[1].span.start..tokens[1].span.end]);
+    }
+
+    #[test]
+    fn should_return_error_for_unrecognized_character(){
+        let source = "let x = @";
+        let error = tokenize(source).unwrap_err();
+        let expected = LexError {
+            kind: LexErrorKind::UnrecognizedCharacter('@'),
+            span: Span::new(8, 9, 1, 9),
+            message: String::from("unrecognized character '@'"),
+            file: None,
+        };
+        assert_eq!(expected, error);
+        assert_eq!("unrecognized character '@' at 1:9", error.to_string());
+    }
+
+    #[test]
+    fn should_keep_producing_same_tokens_for_valid_input(){
+        let source = "let x = 45 * (4 / 3)";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            (TokenType::Let, "let"),
+            (TokenType::Identifier, "x"),
+            (TokenType::Equals, "="),
+            (TokenType::Number, "45"),
+            (TokenType::BinaryOperator, "*"),
+            (TokenType::OpenParen, "("),
+            (TokenType::Number, "4"),
+            (TokenType::BinaryOperator, "/"),
+            (TokenType::Number, "3"),
+            (TokenType::CloseParen, ")"),
+            (TokenType::Eof, ""),
+        ];
+        let actual: Vec<(TokenType, &str)> = tokens.iter().map(|t| (t.kind, t.value.as_ref())).collect();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn should_collect_every_error_in_source_order(){
+        let source = "let a = @\nlet b = 1 $ 2\n~ let c";
+        let (tokens, errors) = tokenize_all(source);
+        let expected = vec![
+            (LexErrorKind::UnrecognizedCharacter('@'), Span::new(8, 9, 1, 9)),
+            (LexErrorKind::UnrecognizedCharacter('$'), Span::new(20, 21, 2, 11)),
+            (LexErrorKind::UnrecognizedCharacter('~'), Span::new(24, 25, 3, 1)),
+        ];
+        let actual: Vec<(LexErrorKind, Span)> = errors.iter().map(|e| (e.kind.clone(), e.span)).collect();
+        assert_eq!(expected, actual, "{:?} should report three errors", errors);
+        // everything around the bad characters is still lexed
+        let expected_len = 11; // code tokens + EOF
+        assert_eq!(
+            expected_len,
+            tokens.len(),
+            "{:?} should have len {} but was {}",
+            tokens,
+            expected_len,
+            tokens.len()
+        );
+    }
+
+    #[test]
+    fn should_match_tokenize_when_there_are_no_errors(){
+        let source = "let x = 45 * (4 / 3)";
+        let (tokens, errors) = tokenize_all(source);
+        assert!(errors.is_empty(), "{:?} should be empty", errors);
+        let collected: Vec<(TokenType, &str, Span)> = tokens.iter().map(|t| (t.kind, t.value.as_ref(), t.span)).collect();
+        let strict = tokenize(source).unwrap();
+        let expected: Vec<(TokenType, &str, Span)> = strict.iter().map(|t| (t.kind, t.value.as_ref(), t.span)).collect();
+        assert_eq!(expected, collected);
+    }
+
+    #[test]
+    fn should_produce_tokens_lazily(){
+        let mut lexer = Lexer::new("let x");
+        let first = lexer.next().unwrap().unwrap();
+        assert_eq!((TokenType::Let, "let"), (first.kind, first.value.as_ref()));
+        let second = lexer.next().unwrap().unwrap();
+        assert_eq!((TokenType::Identifier, "x"), (second.kind, second.value.as_ref()));
+        let eof = lexer.next().unwrap().unwrap();
+        assert_eq!(TokenType::Eof, eof.kind);
+        assert!(lexer.next().is_none(), "lexer should stop after EOF");
+        assert!(lexer.next().is_none(), "lexer should stay finished after EOF");
+    }
+
+    #[test]
+    fn should_stop_iterating_after_error(){
+        let mut lexer = Lexer::new("x @ y");
+        assert!(lexer.next().unwrap().is_ok());
+        let error = lexer.next().unwrap().unwrap_err();
+        assert_eq!(LexErrorKind::UnrecognizedCharacter('@'), error.kind);
+        assert!(lexer.next().is_none(), "lexer should stop after an error");
+    }
+
+    #[test]
+    fn should_relex_identical_tokens_after_rewinding(){
+        let source = "let s = \"a{x}b\"\n(y) / 2 + /re/g";
+        let mut lexer = Lexer::new(source);
+        for _ in 0..5 {
+            lexer.next().unwrap().unwrap();
+        }
+        // inside the interpolation, the state to restore is more than an offset
+        let checkpoint = lexer.checkpoint();
+        let ahead: Vec<Token> = lexer.by_ref().collect::<Result<_, _>>().unwrap();
+        lexer.rewind(checkpoint);
+        let again: Vec<Token> = lexer.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(ahead, again);
+        assert_eq!(tokenize(source).unwrap()[5..], again[..]);
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn should_reproduce_errors_after_rewinding(){
+        let mut lexer = Lexer::new("x @ y");
+        let checkpoint = lexer.checkpoint();
+        let first: Vec<Result<Token, LexError>> = lexer.by_ref().collect();
+        lexer.rewind(checkpoint);
+        assert_eq!(first, lexer.by_ref().collect::<Vec<_>>());
+        // a checkpoint taken after the error keeps the lexer stopped
+        let finished = lexer.checkpoint();
+        lexer.rewind(checkpoint);
+        lexer.rewind(finished);
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn should_collect_lexer_into_same_tokens_as_tokenize(){
+        let source = "let x = 45 * (4 / 3)";
+        let collected: Vec<Token> = Lexer::new(source).collect::<Result<_, _>>().unwrap();
+        let tokens = tokenize(source).unwrap();
+        let expected: Vec<(TokenType, &str, Span)> = tokens.iter().map(|t| (t.kind, t.value.as_ref(), t.span)).collect();
+        let actual: Vec<(TokenType, &str, Span)> = collected.iter().map(|t| (t.kind, t.value.as_ref(), t.span)).collect();
+        assert_eq!(expected, actual);
+        assert_eq!(11, actual.len());
+    }
+
+    #[test]
+    fn should_borrow_token_text_from_source(){
+        let source = String::from("let x = 45");
+        let tokens = tokenize(&source).unwrap();
+        for token in &tokens[..tokens.len() - 1] {
+            match &token.value {
+                Cow::Borrowed(text) => assert_eq!(&source[token.span.start..token.span.end], *text),
+                Cow::Owned(_) => panic!("{:?} should borrow its text", token),
+            }
+        }
+    }
+
+    #[test]
+    fn should_detach_owned_tokens_from_source(){
+        let owned: Vec<Token<'static>> = {
+            let source = String::from("let x");
+            tokenize(&source).unwrap().iter().map(Token::to_owned).collect()
+        };
+        let actual: Vec<(TokenType, &str)> = owned.iter().map(|t| (t.kind, t.value.as_ref())).collect();
+        assert_eq!(vec![(TokenType::Let, "let"), (TokenType::Identifier, "x"), (TokenType::Eof, "")], actual);
+    }
+
+    #[test]
+    fn should_analyze_ternary_punctuation(){
+        let expected = vec![
+            TokenType::Identifier,
+            TokenType::QuestionMark,
+            TokenType::Number,
+            TokenType::Colon,
+            TokenType::Number,
+        ];
+        assert_eq!(expected, lex_kinds("x ? 1 : 2"));
+    }
+
+    #[test]
+    fn should_analyze_brackets_braces_and_commas(){
+        let expected = vec![
+            TokenType::OpenBrace,
+            TokenType::Identifier,
+            TokenType::Colon,
+            TokenType::OpenBracket,
+            TokenType::Number,
+            TokenType::Comma,
+            TokenType::Identifier,
+            TokenType::CloseBracket,
+            TokenType::CloseBrace,
+        ];
+        assert_eq!(expected, lex_kinds("{ a: [1, b] }"));
+        // a `}` outside of any interpolation closes a brace, inside one it
+        // continues the string
+        let expected = vec![TokenType::OpenBrace, TokenType::StringStart, TokenType::Identifier, TokenType::StringEnd, TokenType::CloseBrace];
+        assert_eq!(expected, lex_kinds("{\"a{x}\"}"));
+        // `]` ends a value, `}` doesn't
+        assert_eq!(TokenType::BinaryOperator, lex_kinds("a[0] / 2")[4]);
+        assert_eq!(TokenType::RegexLiteral, lex_kinds("{} /x/")[2]);
+    }
+
+    #[test]
+    fn should_analyze_optional_chaining(){
+        let expected = vec![
+            TokenType::Identifier,
+            TokenType::SafeNavigation,
+            TokenType::Identifier,
+            TokenType::SafeNavigation,
+            TokenType::Identifier,
+            TokenType::BinaryOperator,
+            TokenType::Identifier,
+        ];
+        assert_eq!(expected, lex_kinds("user?.address?.city ?? fallback"));
+        // a ternary whose condition is followed by a space is not chaining
+        assert_eq!(TokenType::QuestionMark, lex_kinds("x ? .5 : 1")[1]);
+    }
+
+    #[test]
+    fn should_analyze_paths(){
+        let expected = vec![
+            TokenType::Identifier,
+            TokenType::PathSeparator,
+            TokenType::Identifier,
+            TokenType::PathSeparator,
+            TokenType::Identifier,
+            TokenType::OpenParen,
+            TokenType::Identifier,
+            TokenType::Colon,
+            TokenType::Identifier,
+            TokenType::CloseParen,
+        ];
+        assert_eq!(expected, lex_kinds("std::math :: sqrt(a : b)"));
+    }
+
+    #[test]
+    fn should_analyze_increment_and_decrement(){
+        let source = "++x x-- + -y --- z";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::PlusPlus, "++"),
+            tok(TokenType::Identifier, "x"),
+            tok(TokenType::Identifier, "x"),
+            tok(TokenType::MinusMinus, "--"),
+            tok(TokenType::BinaryOperator, "+"),
+            tok(TokenType::BinaryOperator, "-"),
+            tok(TokenType::Identifier, "y"),
+            // longest match first: `---` is `--` then `-`
+            tok(TokenType::MinusMinus, "--"),
+            tok(TokenType::BinaryOperator, "-"),
+            tok(TokenType::Identifier, "z"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_analyze_infinity_and_nan_literals(){
+        let source = "let x = Infinity - NaN infinity nan";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Let, "let"),
+            tok(TokenType::Identifier, "x"),
+            tok(TokenType::Equals, "="),
+            tok(TokenType::Infinity, "Infinity"),
+            tok(TokenType::BinaryOperator, "-"),
+            tok(TokenType::NaN, "NaN"),
+            tok(TokenType::Identifier, "infinity"),
+            tok(TokenType::Identifier, "nan"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_analyze_exception_keywords(){
+        let source = "try catch throw thrower";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Try, "try"),
+            tok(TokenType::Catch, "catch"),
+            tok(TokenType::Throw, "throw"),
+            tok(TokenType::Identifier, "thrower"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_analyze_yield_keyword(){
+        let source = "yield n / 2 yield /n/ yields";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Yield, "yield"),
+            tok(TokenType::Identifier, "n"),
+            tok(TokenType::BinaryOperator, "/"),
+            tok(TokenType::Number, "2"),
+            tok(TokenType::Yield, "yield"),
+            tok(TokenType::RegexLiteral, "/n/"),
+            tok(TokenType::Identifier, "yields"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_analyze_async_keywords(){
+        let source = "let v = async (await f) awaited";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Let, "let"),
+            tok(TokenType::Identifier, "v"),
+            tok(TokenType::Equals, "="),
+            tok(TokenType::Async, "async"),
+            tok(TokenType::OpenParen, "("),
+            tok(TokenType::Await, "await"),
+            tok(TokenType::Identifier, "f"),
+            tok(TokenType::CloseParen, ")"),
+            tok(TokenType::Identifier, "awaited"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_analyze_export_keyword(){
+        // `default` is only special after `export`, it stays an identifier
+        let expected = vec![
+            TokenType::Export,
+            TokenType::Let,
+            TokenType::Identifier,
+            TokenType::Export,
+            TokenType::Identifier,
+            TokenType::Identifier,
+            TokenType::Identifier,
+        ];
+        assert_eq!(expected, lex_kinds("export let x export default y exports"));
+    }
+
+    #[test]
+    fn should_analyze_typeof_keyword(){
+        // `typeof` takes an operand, so a `/` after it starts a regex
+        let expected = vec![
+            TokenType::Typeof,
+            TokenType::Identifier,
+            TokenType::Typeof,
+            TokenType::RegexLiteral,
+            TokenType::Identifier,
+        ];
+        assert_eq!(expected, lex_kinds("typeof x typeof /x/ typeofx"));
+    }
+
+    #[test]
+    fn should_analyze_is_keyword(){
+        let expected = vec![
+            TokenType::Identifier,
+            TokenType::Is,
+            TokenType::Identifier,
+            TokenType::Identifier,
+            TokenType::Identifier,
+        ];
+        assert_eq!(expected, lex_kinds("value is Number island isnt"));
+    }
+
+    #[test]
+    fn should_analyze_as_keyword(){
+        let expected = vec![
+            TokenType::Identifier,
+            TokenType::As,
+            TokenType::Identifier,
+            TokenType::Identifier,
+            TokenType::Identifier,
+        ];
+        assert_eq!(expected, lex_kinds("count as String ask has"));
+    }
+
+    #[test]
+    fn should_analyze_const_keyword(){
+        let expected = vec![
+            TokenType::Const,
+            TokenType::Identifier,
+            TokenType::Equals,
+            TokenType::Number,
+            TokenType::Identifier,
+            TokenType::Identifier,
+        ];
+        assert_eq!(expected, lex_kinds("const LIMIT = 10 constant CONST"));
+    }
+
+    #[test]
+    fn should_analyze_defer_keyword(){
+        let expected = vec![
+            TokenType::Defer,
+            TokenType::Identifier,
+            TokenType::Identifier,
+            TokenType::Identifier,
+        ];
+        assert_eq!(expected, lex_kinds("defer close deferred Defer"));
+    }
+
+    #[test]
+    fn should_analyze_with_keyword(){
+        let expected = vec![
+            TokenType::With,
+            TokenType::Identifier,
+            TokenType::Equals,
+            TokenType::Identifier,
+            TokenType::OpenBrace,
+            TokenType::CloseBrace,
+            TokenType::Identifier,
+            TokenType::Identifier,
+        ];
+        assert_eq!(expected, lex_kinds("with file = handle { } without within"));
+    }
+
+    #[test]
+    fn should_analyze_ellipsis(){
+        let source = "(...rest)";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::OpenParen, "("),
+            tok(TokenType::Ellipsis, "..."),
+            tok(TokenType::Identifier, "rest"),
+            tok(TokenType::CloseParen, ")"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+        // a lone dot isn't part of the language
+        let error = tokenize("x..").unwrap_err();
+        assert_eq!(LexErrorKind::UnrecognizedCharacter('.'), error.kind);
+    }
+
+    #[test]
+    fn should_skip_line_comments(){
+        let source = "let x = 4 // four\n// nothing here\nx / 2";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Let, "let"),
+            tok(TokenType::Identifier, "x"),
+            tok(TokenType::Equals, "="),
+            tok(TokenType::Number, "4"),
+            tok(TokenType::Identifier, "x"),
+            tok(TokenType::BinaryOperator, "/"),
+            tok(TokenType::Number, "2"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_split_trivia_at_first_newline(){
+        let source = "x  // one\n\n  y";
+        let tokens = tokenize_preserving_trivia(source).unwrap();
+        let x_trailing = vec![
+            Trivia::new("  ", TriviaKind::Whitespace, Span::new(1, 3, 1, 2)),
+            Trivia::new("// one", TriviaKind::LineComment, Span::new(3, 9, 1, 4)),
+            Trivia::new("\n", TriviaKind::Newline, Span::new(9, 10, 1, 10)),
+        ];
+        let y_leading = vec![
+            Trivia::new("\n", TriviaKind::Newline, Span::new(10, 11, 2, 1)),
+            Trivia::new("  ", TriviaKind::Whitespace, Span::new(11, 13, 3, 1)),
+        ];
+        assert!(tokens[0].leading_trivia.is_empty());
+        assert_eq!(x_trailing, tokens[0].trailing_trivia);
+        assert_eq!(y_leading, tokens[1].leading_trivia);
+        assert!(tokens[1].trailing_trivia.is_empty());
+        assert!(tokens[2].leading_trivia.is_empty());
+    }
+
+    #[test]
+    fn should_reconstruct_source_from_tokens_and_trivia(){
+        let sources = [
+            "// starts with a comment\nlet x = 45 * (4 / 3)  \n\n\tlet y = x // trailing\n// ends with a comment",
+            "  \n",
+            "",
+            "x\r\ny",
+            "let s = \"a\\\n    b{x}\\\r\n c\" + `t\\\nu`",
+        ];
+        for source in sources {
+            let tokens = tokenize_preserving_trivia(source).unwrap();
+            assert_eq!(source, reconstruct(&tokens));
+            // trivia doesn't change the tokens themselves
+            assert!(tokens_eq_ignoring_spans(&tokenize(source).unwrap(), &tokens));
+        }
+        // a continued string drops the continuation from its value only
+        let continued = &tokenize("\"a\\\n  b\"").unwrap()[0];
+        assert_eq!(("\"ab\"", "\"a\\\n  b\""), (continued.value(), continued.raw_text()));
+        assert_eq!("\"a\\\n  b\"", continued.to_owned().raw_text());
+    }
+
+    #[test]
+    fn should_attach_remaining_trivia_to_eof(){
+        let source = "x\n// the end";
+        let tokens = tokenize_preserving_trivia(source).unwrap();
+        let eof = &tokens[1];
+        assert_eq!(TokenType::Eof, eof.kind);
+        assert_eq!(vec![Trivia::new("// the end", TriviaKind::LineComment, Span::new(2, 12, 2, 1))], eof.leading_trivia);
+    }
+
+    #[test]
+    fn should_analyze_string_literals(){
+        let source = r#"let s = "hello, world" "say \"hi\"" "a\{b}""#;
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Let, "let"),
+            tok(TokenType::Identifier, "s"),
+            tok(TokenType::Equals, "="),
+            tok(TokenType::StringLiteral, r#""hello, world""#),
+            tok(TokenType::StringLiteral, r#""say \"hi\"""#),
+            tok(TokenType::StringLiteral, r#""a\{b}""#),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_split_interpolated_strings_around_expressions(){
+        let source = r#""Hello, {name}!" "{a + 1} and {b}" "{}""#;
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::StringStart, r#""Hello, {"#),
+            tok(TokenType::Identifier, "name"),
+            tok(TokenType::StringEnd, r#"}!""#),
+            tok(TokenType::StringStart, r#""{"#),
+            tok(TokenType::Identifier, "a"),
+            tok(TokenType::BinaryOperator, "+"),
+            tok(TokenType::Number, "1"),
+            tok(TokenType::StringMiddle, "} and {"),
+            tok(TokenType::Identifier, "b"),
+            tok(TokenType::StringEnd, r#"}""#),
+            tok(TokenType::StringStart, r#""{"#),
+            tok(TokenType::StringEnd, r#"}""#),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_allow_strings_nested_in_interpolations(){
+        let source = r#""a{ "b{c}" }d""#;
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::StringStart, r#""a{"#),
+            tok(TokenType::StringStart, r#""b{"#),
+            tok(TokenType::Identifier, "c"),
+            tok(TokenType::StringEnd, r#"}""#),
+            tok(TokenType::StringEnd, r#"}d""#),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_report_unterminated_strings(){
+        let error = tokenize("let s = \"abc\nx").unwrap_err();
+        assert_eq!(LexErrorKind::UnterminatedString, error.kind);
+        assert_eq!(Span::new(8, 12, 1, 9), error.span);
+
+        let error = tokenize("\"abc{x").unwrap_err();
+        assert_eq!(LexErrorKind::UnterminatedString, error.kind);
+        assert_eq!(Span::new(6, 6, 1, 7), error.span);
+
+        // a `{` inside an interpolation doesn't open a brace
+        let error = tokenize("\"a{ {x} }\"").unwrap_err();
+        assert_eq!(LexErrorKind::UnrecognizedCharacter('{'), error.kind);
+    }
+
+    #[test]
+    fn should_continue_strings_after_a_backslash_at_the_end_of_a_line(){
+        let source = "let s = \"one \\\n    two\\\r\n three{x}\\\n!\"\nlet";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Let, "let"),
+            tok(TokenType::Identifier, "s"),
+            tok(TokenType::Equals, "="),
+            tok(TokenType::StringStart, "\"one twothree{"),
+            tok(TokenType::Identifier, "x"),
+            tok(TokenType::StringEnd, "}!\""),
+            tok(TokenType::Let, "let"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+        // the span still covers the raw text, continuations included
+        assert_eq!("\"one \\\n    two\\\r\n three{", tokens[3].text(source));
+        assert_eq!((3, 8), (tokens[4].span().line, tokens[4].span().column));
+        assert_eq!((5, 1), (tokens[6].span().line, tokens[6].span().column));
+        // other escapes are left as they are
+        assert_eq!("\"a\\\"b\"", tokenize("\"a\\\"b\"").unwrap()[0].value());
+    }
+
+    #[test]
+    fn should_analyze_multi_line_template_strings(){
+        let source = "let t = `line one\n  two \\` {x}`\nt";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Let, "let"),
+            tok(TokenType::Identifier, "t"),
+            tok(TokenType::Equals, "="),
+            tok(TokenType::TemplateLiteral, "`line one\n  two \\` {x}`"),
+            tok(TokenType::Identifier, "t"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+        assert_eq!((3, 1), (tokens[4].span().line, tokens[4].span().column));
+
+        let source = "`a\r\nb` c";
+        let tokens = tokenize(source).unwrap();
+        assert_eq!("`a\r\nb`", tokens[0].value());
+        assert_eq!((2, 4), (tokens[1].span().line, tokens[1].span().column));
+
+        let error = tokenize("let t = `open\n").unwrap_err();
+        assert_eq!(LexErrorKind::UnterminatedString, error.kind);
+        assert_eq!(Span::new(8, 14, 1, 9), error.span);
+    }
+
+    #[test]
+    fn should_analyze_regex_literals_where_an_operand_is_expected(){
+        let source = "let r = /a[/]\\/b/gi\n(/^x/) / 2 / b";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Let, "let"),
+            tok(TokenType::Identifier, "r"),
+            tok(TokenType::Equals, "="),
+            tok(TokenType::RegexLiteral, "/a[/]\\/b/gi"),
+            tok(TokenType::OpenParen, "("),
+            tok(TokenType::RegexLiteral, "/^x/"),
+            tok(TokenType::CloseParen, ")"),
+            tok(TokenType::BinaryOperator, "/"),
+            tok(TokenType::Number, "2"),
+            tok(TokenType::BinaryOperator, "/"),
+            tok(TokenType::Identifier, "b"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+        assert_eq!(Some(("a[/]\\/b", "gi")), tokens[3].regex_parts());
+        assert_eq!(Some(("^x", "")), tokens[5].regex_parts());
+        assert_eq!(None, tokens[7].regex_parts());
+
+        let tokens = tokenize("\"{/x/}\"").unwrap();
+        assert_eq!(TokenType::RegexLiteral, tokens[1].kind());
+    }
+
+    #[test]
+    fn should_report_unterminated_regex(){
+        let error = tokenize("let r = /ab\n/").unwrap_err();
+        assert_eq!(LexErrorKind::UnterminatedRegex, error.kind);
+        assert_eq!(Span::new(8, 11, 1, 9), error.span);
+        assert_eq!(LexErrorKind::UnterminatedRegex, tokenize("(/[/)").unwrap_err().kind);
+    }
+
+    #[test]
+    fn should_analyze_attribute_marker(){
+        let source = "#derive";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![tok(TokenType::Hash, "#"), tok(TokenType::Identifier, "derive"), eof()];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+    }
+
+    #[test]
+    fn should_analyze_doc_comments(){
+        let source = "/// the answer\nlet x = 42 // not docs\n//// not docs either\n";
+        let tokens = tokenize(source).unwrap();
+        let expected = vec![
+            tok(TokenType::DocComment, "/// the answer"),
+            tok(TokenType::Let, "let"),
+            tok(TokenType::Identifier, "x"),
+            tok(TokenType::Equals, "="),
+            tok(TokenType::Number, "42"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+        assert_eq!(Span::new(0, 14, 1, 1), tokens[0].span());
+    }
+
+    fn error_column(source: &str, config: LexerConfig) -> u32 {
+        let error = Lexer::new(source).with_config(config).find_map(Result::err).unwrap();
+        error.span.column
+    }
+
+    #[test]
+    fn should_count_columns_by_the_configured_tab_width_and_unit(){
+        // `é` is 2 bytes and 1 UTF-16 unit, `😀` is 4 bytes and 2 UTF-16 units
+        let source = "\tlet é = \"😀\" $";
+        let config = |tab_width, column_unit| LexerConfig { tab_width, column_unit, ..LexerConfig::default() };
+        assert_eq!(14, error_column(source, LexerConfig::default()));
+        assert_eq!(17, error_column(source, config(4, ColumnUnit::Chars)));
+        assert_eq!(21, error_column(source, config(8, ColumnUnit::Chars)));
+        assert_eq!(21, error_column(source, config(4, ColumnUnit::Bytes)));
+        assert_eq!(18, error_column(source, config(4, ColumnUnit::Utf16CodeUnits)));
+    }
+
+    #[test]
+    fn should_report_byte_char_and_utf16_columns_after_an_emoji(){
+        // `😀` is 4 bytes, 1 char and 2 UTF-16 code units
+        let source = "let x\nx = \"😀\" $";
+        let config = |column_unit| LexerConfig { column_unit, ..LexerConfig::default() };
+        assert_eq!(12, error_column(source, config(ColumnUnit::Bytes)));
+        assert_eq!(9, error_column(source, config(ColumnUnit::Chars)));
+        assert_eq!(10, error_column(source, config(ColumnUnit::Utf16CodeUnits)));
+
+        let error = tokenize(source).unwrap_err();
+        assert_eq!(9, error.span.column);
+        assert_eq!(10, error.span.utf16_column(source));
+    }
+
+    #[test]
+    fn should_advance_tabs_to_the_next_tab_stop(){
+        let config = LexerConfig { tab_width: 4, ..LexerConfig::default() };
+        let columns: Vec<u32> = Lexer::new("ab\tc\t\td")
+            .with_config(config)
+            .map(|token| token.unwrap().span().column)
+            .collect();
+        assert_eq!(vec![1, 5, 13, 14], columns);
+    }
+
+    #[test]
+    fn should_end_with_an_empty_eof_at_the_end_of_input(){
+        let source = "let é = 1 ";
+        let tokens = tokenize(source).unwrap();
+        let eof = tokens.last().unwrap();
+        assert_eq!(TokenType::Eof, eof.kind());
+        assert_eq!("", eof.value());
+        assert_eq!((source.len(), source.len()), (eof.span().start, eof.span().end));
+    }
+
+    #[test]
+    fn should_leave_out_eof_when_configured(){
+        let source = "let x";
+        let config = LexerConfig { emit_eof: false, ..LexerConfig::default() };
+        let tokens: Vec<Token> = Lexer::new(source).with_config(config).collect::<Result<_, _>>().unwrap();
+        let expected = vec![tok(TokenType::Let, "let"), tok(TokenType::Identifier, "x")];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+        assert!(Lexer::new("").with_config(config).next().is_none());
+    }
+
+    #[test]
+    fn should_turn_unrecognized_characters_into_error_tokens_when_tolerant(){
+        let source = "let x = @@ + 1";
+        let tokens = tokenize_tolerant(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Let, "let"),
+            tok(TokenType::Identifier, "x"),
+            tok(TokenType::Equals, "="),
+            tok(TokenType::Error, "@@"),
+            tok(TokenType::BinaryOperator, "+"),
+            tok(TokenType::Number, "1"),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+        assert_eq!(Span::new(8, 10, 1, 9), tokens[3].span());
+        assert_eq!("`@@` (unrecognized input)", tokens[3].to_string());
+        assert!(tokenize(source).is_err());
+    }
+
+    #[test]
+    fn should_end_error_tokens_at_the_next_valid_token(){
+        let source = "a$$b ~\n.. \"s\"";
+        let tokens = tokenize_tolerant(source).unwrap();
+        let expected = vec![
+            tok(TokenType::Identifier, "a"),
+            tok(TokenType::Error, "$$"),
+            tok(TokenType::Identifier, "b"),
+            tok(TokenType::Error, "~"),
+            tok(TokenType::Error, ".."),
+            tok(TokenType::StringLiteral, "\"s\""),
+            eof(),
+        ];
+        assert!(tokens_eq_ignoring_spans(&expected, &tokens), "{:?} should be {:?}", tokens, expected);
+        assert!(tokenize_tolerant("x = \"open").is_err());
+    }
+
+    #[test]
+    fn should_count_lines_the_same_for_every_line_ending(){
+        let program = ["let a = 1", "", "  let b = a // b"];
+        let positions: Vec<Vec<(u32, u32)>> = ["\n", "\r\n", "\r"]
+            .iter()
+            .map(|ending| {
+                let source = program.join(ending);
+                let tokens = tokenize(&source).unwrap();
+                tokens.iter().map(|token| (token.span().line, token.span().column)).collect()
+            })
+            .collect();
+        assert_eq!(vec![(1, 1), (1, 5), (1, 7), (1, 9), (3, 3), (3, 7), (3, 9), (3, 11), (3, 17)], positions[0]);
+        assert_eq!(positions[0], positions[1]);
+        assert_eq!(positions[0], positions[2]);
+    }
+
+    #[test]
+    fn should_count_lines_with_mixed_line_endings(){
+        let source = "a\r\nb\rc\nd\n\re\r\n\r\nf";
+        let tokens = tokenize(source).unwrap();
+        let positions: Vec<(u32, u32)> = tokens.iter().map(|token| (token.span().line, token.span().column)).collect();
+        assert_eq!(vec![(1, 1), (2, 1), (3, 1), (4, 1), (6, 1), (8, 1), (8, 2)], positions);
+
+        let trivia = tokenize_preserving_trivia("a\r\nb").unwrap();
+        assert_eq!(vec![Trivia::new("\r\n", TriviaKind::Newline, Span::new(1, 3, 1, 2))], trivia[0].trailing_trivia);
+        assert_eq!(LexErrorKind::UnterminatedString, tokenize("\"ab\rc\"").unwrap_err().kind);
+    }
+
+    // longest match: an operator sharing a prefix with a longer one is only
+    // produced when the longer one doesn't fit
+    mod munch{
+        use super::lex_kinds;
+        use crate::lexer::tokenize;
+        use crate::lexer::TokenType::{
+            self, Bang, BinaryOperator, CloseParen, Colon, DocComment, Ellipsis, Equals, Error, Identifier, MinusMinus, OpenParen,
+            PathSeparator, PlusPlus, QuestionMark, SafeNavigation,
+        };
+
+        // source, kinds it lexes into; rows for operators that don't exist yet
+        // (`<<`, `->`, `..`) record what their characters lex as today and
+        // change when the operator lands
+        const TABLE: &[(&str, &[TokenType])] = &[
+            // `++` and `--` are taken first, whatever follows them
+            ("+++", &[PlusPlus, BinaryOperator]),
+            ("++++", &[PlusPlus, PlusPlus]),
+            ("---", &[MinusMinus, BinaryOperator]),
+            ("----", &[MinusMinus, MinusMinus]),
+            ("++--", &[PlusPlus, MinusMinus]),
+            ("+-", &[BinaryOperator, BinaryOperator]),
+            ("-+", &[BinaryOperator, BinaryOperator]),
+            ("+ +", &[BinaryOperator, BinaryOperator]),
+            // no arrow, `>` isn't an operator
+            ("-->", &[MinusMinus, Error]),
+            ("->", &[BinaryOperator, Error]),
+            // `==` is the only comparison and there is no compound assignment
+            ("==", &[BinaryOperator]),
+            ("===", &[BinaryOperator, Equals]),
+            ("====", &[BinaryOperator, BinaryOperator]),
+            ("**", &[BinaryOperator]),
+            ("***", &[BinaryOperator, BinaryOperator]),
+            ("**=", &[BinaryOperator, Equals]),
+            ("=+", &[Equals, BinaryOperator]),
+            ("+=", &[BinaryOperator, Equals]),
+            // no `!=`, `!` is always alone
+            ("!=", &[Bang, Equals]),
+            ("!==", &[Bang, BinaryOperator]),
+            ("!!", &[Bang, Bang]),
+            // `/` is only an operator after a value, on its own it starts a regex
+            ("x/=y", &[Identifier, BinaryOperator, Equals, Identifier]),
+            // no shifts or comparisons, `<` and `>` are unrecognized, an `=`
+            // after them is not
+            ("<=", &[Error, Equals]),
+            ("<<=", &[Error, Equals]),
+            (">>=", &[Error, Equals]),
+            // only three dots make an operator, a lone dot or two are unrecognized
+            ("...", &[Ellipsis]),
+            ("....", &[Ellipsis, Error]),
+            ("......", &[Ellipsis, Ellipsis]),
+            ("..", &[Error]),
+            ("..=.", &[Error, Equals, Error]),
+            // `//` is a comment, `///` a doc comment even right after a value,
+            // and four slashes or more a comment again
+            ("x//y", &[Identifier]),
+            ("x///y", &[Identifier, DocComment]),
+            ("x////y", &[Identifier]),
+            // two colons are a path separator, an odd one out is a colon
+            ("::", &[PathSeparator]),
+            (":::", &[PathSeparator, Colon]),
+            ("::::", &[PathSeparator, PathSeparator]),
+            ("?::", &[QuestionMark, PathSeparator]),
+            // `?` takes a dot or a second `?`, whichever comes first
+            ("?.", &[SafeNavigation]),
+            ("?..", &[SafeNavigation, Error]),
+            ("??", &[BinaryOperator]),
+            ("???", &[BinaryOperator, QuestionMark]),
+            ("??.", &[BinaryOperator, Error]),
+            ("?.?", &[SafeNavigation, QuestionMark]),
+            // single-character operators never combine
+            ("?:", &[QuestionMark, Colon]),
+            ("()", &[OpenParen, CloseParen]),
+        ];
+
+        #[test]
+        fn should_take_the_longest_operator(){
+            for (source, expected) in TABLE {
+                assert_eq!(expected.to_vec(), lex_kinds(source), "{:?}", source);
+            }
+        }
+
+        #[test]
+        fn should_lex_the_operators_of_a_table_row_with_or_without_spaces(){
+            // the table rows split into the same kinds when spaced out as lexed
+            assert_eq!(lex_kinds("a ++ + b"), lex_kinds("a+++b"));
+            assert_eq!(lex_kinds("a -- - b"), lex_kinds("a---b"));
+            assert_eq!(lex_kinds("... ..."), lex_kinds("......"));
+        }
+
+        #[test]
+        fn should_tell_doubled_operators_apart_by_text(){
+            // the kinds of `**` and `* *` are the same, only the text differs
+            for (source, expected) in [("***", ["**", "*"]), ("===", ["==", "="]), ("= ==", ["=", "=="]), ("???", ["??", "?"])] {
+                let tokens = tokenize(source).unwrap();
+                let texts: Vec<&str> = tokens.iter().map(|token| token.value()).collect();
+                assert_eq!(expected.as_slice(), &texts[..2], "{:?}", source);
+            }
+        }
+    }
+
+    // characters the lexer treats specially, so random sources reach strings,
+    // interpolations and comments much more often than plain random text would
+    fn source_strategy() -> impl Strategy<Value = String> {
+        let special = prop::sample::select(vec!['"', '{', '}', '\\', '/', '.', '+', '-', '\n', '\r', ' ', '1', 'a', '#', '`']);
+        prop::collection::vec(prop_oneof![any::<char>(), special], 0..64).prop_map(|chars| chars.into_iter().collect())
+    }
+
+    // programs put together from pieces the lexer knows, each one lexes on its
+    // own and keeps lexing when followed by any separator
+    fn program_strategy() -> impl Strategy<Value = String> {
+        let mut pieces = vec![
+            "x", "someName", "0", "4567", "(", ")", "=", "==", "+", "-", "*", "**", "%", "!", "??", "?.", "[", "]", "{", "}", ",", "++", "--", "?", ":", "::", "...", "#",
+            "a / 2", "= /a[/]b\\/c/gi", r#""plain""#, r#""say \"hi\"""#, r#""a{x}b{(y)}c""#, "\"con\\\n   tinued\"",
+            "\"crlf\\\r\ncontinued\"", "`template\nwith {braces}`", "// comment\n", "/// doc comment\n",
+            "//// not doc\r\n", "é", "日本",
+        ];
+        pieces.extend(keywords().iter().map(|&(word, _)| word));
+        let separator = prop::sample::select(vec![" ", "  ", "\t", "\n", "\r\n", "\r", "\n\n  "]);
+        prop::collection::vec((prop::sample::select(pieces), separator), 0..32)
+            .prop_map(|parts| parts.into_iter().flat_map(|(piece, separator)| [piece, separator]).collect())
+    }
+
+    fn assert_well_formed(source: &str, tokens: &[Token]) {
+        let (last, rest) = tokens.split_last().expect("a successful lex yields at least EOF");
+        assert_eq!(TokenType::Eof, last.kind(), "{:?} should end with EOF", tokens);
+        assert!(rest.iter().all(|token| token.kind() != TokenType::Eof), "{:?} has EOF before the end", tokens);
+        for token in tokens {
+            let span = token.span();
+            assert!(source.get(span.start..span.end).is_some(), "{:?} is not a valid range of {:?}", span, source);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn should_never_panic_on_arbitrary_input(source in source_strategy()) {
+            if let Ok(tokens) = tokenize(&source) {
+                assert_well_formed(&source, &tokens);
+            }
+            if let Ok(tokens) = tokenize_preserving_trivia(&source) {
+                assert_well_formed(&source, &tokens);
+            }
+            if let Ok(tokens) = tokenize_tolerant(&source) {
+                assert_well_formed(&source, &tokens);
+            }
+            let (tokens, _) = tokenize_all(&source);
+            assert_well_formed(&source, &tokens);
+        }
+
+        #[test]
+        fn should_reconstruct_any_program_exactly(source in program_strategy()) {
+            let tokens = tokenize_preserving_trivia(&source).unwrap();
+            prop_assert_eq!(&source, &reconstruct(&tokens));
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::lexer::{tokenize, LexError, Lexer, LexerConfig, Span, Token};
+
+/// Source text along with the offset of every line start, so that offsets are
+/// turned into lines and columns by a binary search instead of a rescan.
+///
+/// Lines end at `\n`, `\r` or `\r\n`, like in the lexer. Lines and columns are
+/// 1-based and columns are counted in characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    name: String,
+    text: String,
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new(name: impl Into<String>, text: impl Into<String>) -> Self {
+        let text = text.into();
+        let line_starts = line_starts(&text);
+        SourceFile { name: name.into(), text, line_starts }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Offset of the first byte of every line, the first one is always 0.
+    pub fn line_starts(&self) -> &[usize] {
+        &self.line_starts
+    }
+
+    /// Number of lines, an empty file and a file ending with a line break
+    /// count the empty line at the end.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Line and column of the byte at `offset`, which may also be the end of
+    /// the text.
+    ///
+    /// # Panics
+    ///
+    /// If `offset` is past the end of the text or not on a char boundary.
+    pub fn line_col(&self, offset: usize) -> (u32, u32) {
+        line_col(&self.text, &self.line_starts, offset)
+    }
+
+    /// Text of `line` without its line break, `None` past the last line.
+    pub fn line_text(&self, line: u32) -> Option<&str> {
+        let index = (line as usize).checked_sub(1)?;
+        let start = *self.line_starts.get(index)?;
+        let end = self.line_starts.get(index + 1).copied().unwrap_or(self.text.len());
+        Some(self.text[start..end].trim_end_matches(['\n', '\r']))
+    }
+
+    /// Offset of the character at `line` and `column`. The column right after
+    /// the last character of the line is its end, anything further is `None`.
+    pub fn offset_of(&self, line: u32, column: u32) -> Option<usize> {
+        let text = self.line_text(line)?;
+        let start = self.line_starts[line as usize - 1];
+        let index = (column as usize).checked_sub(1)?;
+        match text.char_indices().nth(index) {
+            Some((offset, _)) => Some(start + offset),
+            None if index == text.chars().count() => Some(start + text.len()),
+            None => None,
+        }
+    }
+
+    /// Like [`tokenize`], errors record the name of the file.
+    pub fn tokenize(&self) -> Result<Vec<Token<'_>>, LexError> {
+        tokenize(&self.text).map_err(|mut err| {
+            err.file = Some(self.name.clone());
+            err
+        })
+    }
+
+    /// Like [`SourceFile::tokenize`], but the lexer doesn't track lines and
+    /// columns: token spans only hold byte offsets, their line and column are
+    /// 0 and [`SourceFile::line_col`] gives them when they are needed. Errors
+    /// still get their line and column.
+    pub fn tokenize_offsets(&self) -> Result<Vec<Token<'_>>, LexError> {
+        let config = LexerConfig { positions: false, ..LexerConfig::default() };
+        Lexer::new(&self.text).with_config(config).collect::<Result<_, _>>().map_err(|mut err: LexError| {
+            (err.span.line, err.span.column) = self.line_col(err.span.start);
+            err.file = Some(self.name.clone());
+            err
+        })
+    }
+}
+
+/// Handle of a file added to a [`SourceManager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(u32);
+
+/// Byte range in one of the files of a [`SourceManager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileSpan {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl FileSpan {
+    /// The range of `span`, which was lexed from `file`.
+    pub fn new(file: FileId, span: Span) -> Self {
+        FileSpan { file, start: span.start, end: span.end }
+    }
+}
+
+/// Where a [`FileSpan`] starts, displayed as `path:line:col`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location<'a> {
+    pub name: &'a str,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.name, self.line, self.column)
+    }
+}
+
+/// Owns the files of a program, each one known by the [`FileId`] it was given
+/// when added.
+#[derive(Debug, Clone, Default)]
+pub struct SourceManager {
+    files: Vec<SourceFile>,
+}
+
+impl SourceManager {
+    pub fn new() -> Self {
+        SourceManager::default()
+    }
+
+    pub fn add(&mut self, file: SourceFile) -> FileId {
+        let id = FileId(u32::try_from(self.files.len()).expect("too many source files"));
+        self.files.push(file);
+        id
+    }
+
+    /// The file `id` was given to. `id` must come from this manager.
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0 as usize]
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = (FileId, &SourceFile)> {
+        self.files.iter().enumerate().map(|(index, file)| (FileId(index as u32), file))
+    }
+
+    /// File name, line and column of the start of `span`.
+    pub fn resolve(&self, span: FileSpan) -> Location<'_> {
+        let file = self.file(span.file);
+        let (line, column) = file.line_col(span.start);
+        Location { name: file.name(), line, column }
+    }
+
+    /// Tokens of the file `id`, see [`SourceFile::tokenize`].
+    pub fn tokenize(&self, id: FileId) -> Result<Vec<Token<'_>>, LexError> {
+        self.file(id).tokenize()
+    }
+}
+
+// offset of every line, a line ends at `\n`, `\r` or `\r\n`
+pub(crate) fn line_starts(text: &str) -> Vec<usize> {
+    let bytes = text.as_bytes();
+    let mut starts = Vec::from([0]);
+    for (i, &byte) in bytes.iter().enumerate() {
+        let ends_line = byte == b'\n' || (byte == b'\r' && bytes.get(i + 1) != Some(&b'\n'));
+        if ends_line {
+            starts.push(i + 1);
+        }
+    }
+    starts
+}
+
+// line and character column of `offset` in `text`, whose line starts are
+// `line_starts`
+pub(crate) fn line_col(text: &str, line_starts: &[usize], offset: usize) -> (u32, u32) {
+    assert!(text.is_char_boundary(offset), "offset {} is not a char boundary of the text", offset);
+    // the first entry is 0, so there is always a line at or before `offset`
+    let line = line_starts.partition_point(|&line_start| line_start <= offset);
+    let column = text[line_starts[line - 1]..offset].chars().count() + 1;
+    (line as u32, column as u32)
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::lexer::source::{FileSpan, SourceFile, SourceManager};
+    use crate::lexer::tokenize;
+
+    #[test]
+    fn should_locate_offsets_at_line_boundaries(){
+        let file = SourceFile::new("main.lang", "ab\ncd\r\né\rf");
+        assert_eq!(&[0, 3, 7, 10], file.line_starts());
+        assert_eq!((1, 1), file.line_col(0));
+        // the line break belongs to the line it ends
+        assert_eq!((1, 3), file.line_col(2));
+        assert_eq!((2, 1), file.line_col(3));
+        assert_eq!((2, 3), file.line_col(5));
+        assert_eq!((2, 4), file.line_col(6));
+        assert_eq!((3, 1), file.line_col(7));
+        assert_eq!((3, 2), file.line_col(9));
+        assert_eq!((4, 1), file.line_col(10));
+    }
+
+    #[test]
+    fn should_locate_the_last_byte_and_the_end_of_the_file(){
+        let file = SourceFile::new("main.lang", "x\nyz");
+        assert_eq!((2, 2), file.line_col(3));
+        assert_eq!((2, 3), file.line_col(4));
+        let file = SourceFile::new("main.lang", "x\n");
+        assert_eq!(2, file.line_count());
+        assert_eq!((1, 2), file.line_col(1));
+        assert_eq!((2, 1), file.line_col(2));
+        assert_eq!(Some(""), file.line_text(2));
+    }
+
+    #[test]
+    fn should_have_a_single_empty_line_in_an_empty_file(){
+        let file = SourceFile::new("empty.lang", "");
+        assert_eq!(1, file.line_count());
+        assert_eq!((1, 1), file.line_col(0));
+        assert_eq!(Some(""), file.line_text(1));
+        assert_eq!(Some(0), file.offset_of(1, 1));
+        assert_eq!(None, file.offset_of(1, 2));
+        assert_eq!(None, file.line_text(2));
+    }
+
+    #[test]
+    fn should_return_line_text_without_the_line_break(){
+        let file = SourceFile::new("main.lang", "let x\r\n\r\nx\r");
+        let lines: Vec<Option<&str>> = (0..=5).map(|line| file.line_text(line)).collect();
+        assert_eq!(vec![None, Some("let x"), Some(""), Some("x"), Some(""), None], lines);
+    }
+
+    #[test]
+    fn should_turn_lines_and_columns_back_into_offsets(){
+        let file = SourceFile::new("main.lang", "ab\nçd\n");
+        assert_eq!(Some(1), file.offset_of(1, 2));
+        assert_eq!(Some(2), file.offset_of(1, 3));
+        assert_eq!(None, file.offset_of(1, 4));
+        assert_eq!(Some(5), file.offset_of(2, 2));
+        assert_eq!(None, file.offset_of(2, 0));
+        assert_eq!(None, file.offset_of(4, 1));
+        for offset in [0, 1, 2, 3, 5, 6, 7] {
+            let (line, column) = file.line_col(offset);
+            assert_eq!(Some(offset), file.offset_of(line, column));
+        }
+    }
+
+    #[test]
+    fn should_agree_with_the_lexer_positions(){
+        let file = SourceFile::new("main.lang", "let é = \"a{b}c\"\r\nx\rnull\n\n// done\n(y)");
+        for token in tokenize(file.text()).unwrap() {
+            let span = token.span();
+            assert_eq!((span.line, span.column), file.line_col(span.start), "{:?}", token);
+        }
+    }
+
+    #[test]
+    fn should_leave_positions_to_the_file_when_lexing_offsets_only(){
+        let file = SourceFile::new("main.lang", "let é = \"a{b}c\"\r\nx\rnull\n\n// done\n`y\n`");
+        let tokens = file.tokenize_offsets().unwrap();
+        let expected = tokenize(file.text()).unwrap();
+        assert_eq!(expected.len(), tokens.len());
+        for (token, expected) in tokens.iter().zip(&expected) {
+            let (span, expected_span) = (token.span(), expected.span());
+            assert_eq!((expected.kind(), expected.value()), (token.kind(), token.value()));
+            assert_eq!((expected_span.start, expected_span.end, 0, 0), (span.start, span.end, span.line, span.column));
+            assert_eq!((expected_span.line, expected_span.column), file.line_col(span.start));
+        }
+
+        let error = SourceFile::new("main.lang", "let y = 2\nlet z @ 3").tokenize_offsets().unwrap_err();
+        assert_eq!("main.lang:2:7: unrecognized character '@'", error.to_string());
+    }
+
+    #[test]
+    fn should_report_errors_in_the_file_they_come_from(){
+        let mut sources = SourceManager::new();
+        let first = sources.add(SourceFile::new("main.lang", "let x = 1"));
+        let second = sources.add(SourceFile::new("lib/util.lang", "let y = 2\nlet z @ 3"));
+        assert_ne!(first, second);
+        assert!(sources.tokenize(first).is_ok());
+
+        let error = sources.tokenize(second).unwrap_err();
+        assert_eq!(Some("lib/util.lang"), error.file.as_deref());
+        assert_eq!("lib/util.lang:2:7: unrecognized character '@'", error.to_string());
+        let location = sources.resolve(FileSpan::new(second, error.span));
+        assert_eq!("lib/util.lang:2:7", location.to_string());
+    }
+
+    #[test]
+    fn should_resolve_token_spans_to_their_file(){
+        let mut sources = SourceManager::new();
+        let first = sources.add(SourceFile::new("a.lang", "x"));
+        let second = sources.add(SourceFile::new("b.lang", "\n\n  y"));
+        let tokens = sources.tokenize(second).unwrap();
+        let location = sources.resolve(FileSpan::new(second, tokens[0].span()));
+        assert_eq!(("b.lang", 3, 3), (location.name, location.line, location.column));
+        let names: Vec<&str> = sources.files().map(|(_, file)| file.name()).collect();
+        assert_eq!(vec!["a.lang", "b.lang"], names);
+        assert_eq!("x", sources.file(first).text());
+    }
+}
@@ -0,0 +1,110 @@
+use alloc::collections::BTreeSet;
+use core::fmt;
+
+use crate::lexer::{Token, TokenType};
+
+/// Quick metrics over a token list, for tooling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TokenStats {
+    /// Every token except EOF.
+    pub tokens: usize,
+    pub identifiers: usize,
+    pub distinct_identifiers: usize,
+    pub max_paren_depth: usize,
+    /// A `)` came without a matching `(`, or a `(` was never closed.
+    pub unbalanced_parens: bool,
+    /// Lines holding at least one token other than a doc comment; blank lines
+    /// and comment-only lines are not counted.
+    pub lines_of_code: usize,
+}
+
+impl TokenStats {
+    pub fn from_tokens(tokens: &[Token<'_>]) -> Self {
+        let mut stats = TokenStats::default();
+        let mut names = BTreeSet::new();
+        let mut lines = BTreeSet::new();
+        let mut depth: usize = 0;
+        for token in tokens {
+            match token.kind {
+                TokenType::Eof => continue,
+                TokenType::Identifier => {
+                    stats.identifiers += 1;
+                    names.insert(token.value());
+                }
+                TokenType::OpenParen => {
+                    depth += 1;
+                    stats.max_paren_depth = stats.max_paren_depth.max(depth);
+                }
+                // a stray `)` is reported, the depth stays at zero
+                TokenType::CloseParen => match depth.checked_sub(1) {
+                    Some(outer) => depth = outer,
+                    None => stats.unbalanced_parens = true,
+                },
+                _ => {}
+            }
+            stats.tokens += 1;
+            if token.kind != TokenType::DocComment {
+                lines.insert(token.span.line);
+            }
+        }
+        stats.unbalanced_parens |= depth > 0;
+        stats.distinct_identifiers = names.len();
+        stats.lines_of_code = lines.len();
+        stats
+    }
+}
+
+/// Short multi-line report, one metric per line.
+impl fmt::Display for TokenStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tokens: {}", self.tokens)?;
+        writeln!(f, "identifiers: {} ({} distinct)", self.identifiers, self.distinct_identifiers)?;
+        write!(f, "max paren depth: {}", self.max_paren_depth)?;
+        if self.unbalanced_parens {
+            write!(f, " (unbalanced)")?;
+        }
+        writeln!(f)?;
+        write!(f, "lines of code: {}", self.lines_of_code)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::lexer::stats::TokenStats;
+    use crate::lexer::tokenize;
+
+    fn stats(source: &str) -> TokenStats {
+        TokenStats::from_tokens(&tokenize(source).unwrap())
+    }
+
+    #[test]
+    fn should_count_a_known_program(){
+        let source = "/// totals\nlet total = (a + (b * 2))\n\n// tax\nlet tax = total * rate\n";
+        let expected = TokenStats {
+            tokens: 19,
+            identifiers: 6,
+            distinct_identifiers: 5,
+            max_paren_depth: 2,
+            unbalanced_parens: false,
+            lines_of_code: 2,
+        };
+        assert_eq!(expected, stats(source));
+        let report = "tokens: 19\nidentifiers: 6 (5 distinct)\nmax paren depth: 2\nlines of code: 2";
+        assert_eq!(report, stats(source).to_string());
+    }
+
+    #[test]
+    fn should_flag_unbalanced_parens(){
+        assert!(stats("(a))").unbalanced_parens);
+        assert_eq!(1, stats("(a))").max_paren_depth);
+        assert!(stats("((a)").unbalanced_parens);
+        assert_eq!(2, stats("((a)").max_paren_depth);
+        assert!(stats(")(").unbalanced_parens);
+        assert_eq!("max paren depth: 1 (unbalanced)", stats(")(").to_string().lines().nth(2).unwrap());
+    }
+
+    #[test]
+    fn should_count_nothing_for_empty_source(){
+        assert_eq!(TokenStats::default(), stats("\n// only a comment\n"));
+    }
+}
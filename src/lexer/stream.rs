@@ -0,0 +1,398 @@
+use alloc::collections::VecDeque;
+use core::fmt;
+use core::iter::FusedIterator;
+
+use crate::lexer::{count_line_breaks, is_line_break, Checkpoint, LexError, Lexer, Span, Token, TokenType};
+
+/// Error for a token of another kind than the one expected, see
+/// [`TokenStream::expect`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenError {
+    /// Span of the token that was found.
+    pub span: Span,
+    pub found: TokenType,
+    pub expected: TokenType,
+}
+
+/// `expected ')', found 'let' at 1:14`
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, found {} at {}:{}", self.expected, self.found, self.span.line, self.span.column)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TokenError {}
+
+/// Saved position of a [`TokenStream`], see [`TokenStream::checkpoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenStreamCheckpoint {
+    current: usize,
+}
+
+/// Cursor over a slice of lexed tokens, borrowed rather than copied.
+///
+/// The stream always ends with an EOF token: once the cursor reaches it, peeking
+/// and advancing keep returning that same EOF token.
+pub struct TokenStream<'t, 'src> {
+    // the tokens before EOF
+    tokens: &'t [Token<'src>],
+    eof: Token<'src>,
+    current: usize,
+}
+
+impl<'t, 'src> TokenStream<'t, 'src> {
+    pub fn new(tokens: &'t [Token<'src>]) -> Self {
+        let (tokens, eof) = match tokens.split_last() {
+            Some((last, rest)) if last.kind == TokenType::Eof => (rest, last.clone()),
+            // slices lexed without `emit_eof`, or built by hand, lack the EOF token
+            _ => {
+                let span = tokens.last().map_or(Span::new(0, 0, 1, 1), span_after);
+                (tokens, Token::new("", TokenType::Eof, span))
+            }
+        };
+        TokenStream { tokens, eof, current: 0 }
+    }
+
+    pub fn peek(&self) -> &Token<'src> {
+        self.peek_n(0)
+    }
+
+    /// Looks `k` tokens ahead without consuming anything, `peek_n(0)` is the
+    /// current token. Looking past the end returns the EOF token.
+    pub fn peek_n(&self, k: usize) -> &Token<'src> {
+        self.token_at(self.current + k)
+    }
+
+    // not an `Iterator`: the returned token borrows the stream and EOF repeats forever
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> &Token<'src> {
+        let index = self.current;
+        if !self.at_eof() {
+            self.current += 1;
+        }
+        self.token_at(index)
+    }
+
+    /// The last token consumed, `None` before the first one.
+    pub fn previous(&self) -> Option<&Token<'src>> {
+        self.current.checked_sub(1).map(|index| &self.tokens[index])
+    }
+
+    pub fn check(&self, kind: TokenType) -> bool {
+        self.peek().kind == kind
+    }
+
+    /// Consumes the current token if it has the given kind, otherwise reports
+    /// what was found instead and leaves the stream untouched.
+    pub fn expect(&mut self, kind: TokenType) -> Result<&Token<'src>, TokenError> {
+        if self.check(kind) {
+            Ok(self.next())
+        } else {
+            let found = self.peek();
+            Err(TokenError { span: found.span, found: found.kind, expected: kind })
+        }
+    }
+
+    pub fn at_eof(&self) -> bool {
+        self.check(TokenType::Eof)
+    }
+
+    /// Index of the current token, to come back to it once more has been
+    /// parsed, see [`TokenStream::token_at`].
+    pub fn position(&self) -> usize {
+        self.current
+    }
+
+    /// The token at `position`, the EOF token past the end.
+    pub fn token_at(&self, position: usize) -> &Token<'src> {
+        self.tokens.get(position).unwrap_or(&self.eof)
+    }
+
+    /// Saves the current position so that [`TokenStream::rewind`] can come
+    /// back to it.
+    pub fn checkpoint(&self) -> TokenStreamCheckpoint {
+        TokenStreamCheckpoint { current: self.current }
+    }
+
+    /// Goes back to a position saved by [`TokenStream::checkpoint`] on this
+    /// stream.
+    pub fn rewind(&mut self, checkpoint: TokenStreamCheckpoint) {
+        self.current = checkpoint.current.min(self.tokens.len());
+    }
+}
+
+// empty span right where `token` ends in the source, its raw text is exactly
+// what it spans; columns count chars like the default `LexerConfig`
+fn span_after(token: &Token<'_>) -> Span {
+    let text = token.raw_text();
+    let lines = count_line_breaks(text);
+    let column = match text.rfind(is_line_break) {
+        Some(index) => 1 + text[index + 1..].chars().count() as u32,
+        None => token.span.column + text.chars().count() as u32,
+    };
+    Span::new(token.span.end, token.span.end, token.span.line + lines, column)
+}
+
+/// Saved state of a [`Lookahead`], see [`Lookahead::checkpoint`].
+#[derive(Debug, Clone)]
+pub struct LookaheadCheckpoint<'src> {
+    lexer: Checkpoint,
+    buffer: VecDeque<Result<Token<'src>, LexError>>,
+}
+
+/// Lexer with arbitrary lookahead, tokens are scanned only when they are
+/// peeked at or consumed.
+///
+/// Peeked results wait in a ring buffer until `next` hands them out, so every
+/// result is produced exactly once and in order. Like the [`Lexer`] it wraps,
+/// it ends after EOF or the first error, and peeking past that end returns
+/// `None`.
+pub struct Lookahead<'src> {
+    lexer: Lexer<'src>,
+    buffer: VecDeque<Result<Token<'src>, LexError>>,
+}
+
+impl<'src> Lookahead<'src> {
+    pub fn new(lexer: Lexer<'src>) -> Self {
+        Lookahead { lexer, buffer: VecDeque::new() }
+    }
+
+    pub fn peek(&mut self) -> Option<&Token<'src>> {
+        self.peek_n(0)
+    }
+
+    /// The token `n` places ahead, `peek_n(0)` is the one `next` returns.
+    /// `None` past the end, or when the lexer fails before reaching it.
+    pub fn peek_n(&mut self, n: usize) -> Option<&Token<'src>> {
+        while self.buffer.len() <= n {
+            match self.lexer.next() {
+                Some(result) => self.buffer.push_back(result),
+                None => break,
+            }
+        }
+        self.buffer.get(n)?.as_ref().ok()
+    }
+
+    /// Saves the current state, peeked tokens included, so that
+    /// [`Lookahead::rewind`] can come back to it.
+    pub fn checkpoint(&self) -> LookaheadCheckpoint<'src> {
+        LookaheadCheckpoint { lexer: self.lexer.checkpoint(), buffer: self.buffer.clone() }
+    }
+
+    /// Goes back to a state saved by [`Lookahead::checkpoint`] on this stream.
+    pub fn rewind(&mut self, checkpoint: LookaheadCheckpoint<'src>) {
+        self.lexer.rewind(checkpoint.lexer);
+        self.buffer = checkpoint.buffer;
+    }
+}
+
+impl<'src> Iterator for Lookahead<'src> {
+    type Item = Result<Token<'src>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buffer.pop_front().or_else(|| self.lexer.next())
+    }
+}
+
+impl FusedIterator for Lookahead<'_> {}
+
+#[cfg(test)]
+mod tests{
+    use crate::lexer::{tokenize, LexError, Lexer, Span, Token, TokenType};
+    use crate::lexer::stream::{Lookahead, TokenError, TokenStream};
+    use crate::lexer::LexerConfig;
+
+    #[test]
+    fn should_peek_without_consuming(){
+        let tokens = tokenize("let x").unwrap();
+        let tokens = TokenStream::new(&tokens);
+        assert_eq!(TokenType::Let, tokens.peek().kind);
+        assert_eq!(TokenType::Let, tokens.peek().kind);
+        assert_eq!(TokenType::Identifier, tokens.peek_n(1).kind);
+        assert_eq!(TokenType::Eof, tokens.peek_n(2).kind);
+    }
+
+    #[test]
+    fn should_return_eof_when_peeking_past_end(){
+        let tokens = tokenize("x").unwrap();
+        let tokens = TokenStream::new(&tokens);
+        assert_eq!(TokenType::Eof, tokens.peek_n(1).kind);
+        assert_eq!(TokenType::Eof, tokens.peek_n(100).kind);
+    }
+
+    #[test]
+    fn should_advance_with_next(){
+        let tokens = tokenize("let x").unwrap();
+        let mut tokens = TokenStream::new(&tokens);
+        assert_eq!("let", tokens.next().value);
+        assert_eq!("x", tokens.next().value);
+        assert!(tokens.at_eof());
+        assert_eq!(TokenType::Eof, tokens.next().kind);
+        assert_eq!(TokenType::Eof, tokens.next().kind);
+    }
+
+    #[test]
+    fn should_check_current_kind(){
+        let tokens = tokenize("( )").unwrap();
+        let mut tokens = TokenStream::new(&tokens);
+        assert!(tokens.check(TokenType::OpenParen));
+        assert!(!tokens.check(TokenType::CloseParen));
+        tokens.next();
+        assert!(tokens.check(TokenType::CloseParen));
+        assert!(!tokens.at_eof());
+    }
+
+    #[test]
+    fn should_remember_the_last_consumed_token(){
+        let tokens = tokenize("let x").unwrap();
+        let mut tokens = TokenStream::new(&tokens);
+        assert!(tokens.previous().is_none());
+        tokens.next();
+        assert_eq!(Some(TokenType::Let), tokens.previous().map(|token| token.kind));
+        tokens.next();
+        tokens.next();
+        // EOF is never consumed
+        assert_eq!(Some(TokenType::Identifier), tokens.previous().map(|token| token.kind));
+    }
+
+    #[test]
+    fn should_consume_expected_token(){
+        let tokens = tokenize("let x").unwrap();
+        let mut tokens = TokenStream::new(&tokens);
+        let token = tokens.expect(TokenType::Let).unwrap();
+        assert_eq!("let", token.value);
+        assert!(tokens.check(TokenType::Identifier));
+    }
+
+    #[test]
+    fn should_report_expected_and_found_tokens(){
+        let tokens = tokenize("let = 1").unwrap();
+        let mut tokens = TokenStream::new(&tokens);
+        tokens.next();
+        let error = tokens.expect(TokenType::Identifier).unwrap_err();
+        let expected = TokenError { span: Span::new(4, 5, 1, 5), found: TokenType::Equals, expected: TokenType::Identifier };
+        assert_eq!(expected, error);
+        assert_eq!("expected identifier, found '=' at 1:5", error.to_string());
+        // failed expectations don't move the cursor
+        assert!(tokens.check(TokenType::Equals));
+    }
+
+    #[test]
+    fn should_return_the_token_at_an_earlier_position(){
+        let tokens = tokenize("(a) = 1").unwrap();
+        let mut tokens = TokenStream::new(&tokens);
+        let start = tokens.position();
+        tokens.next();
+        tokens.next();
+        assert_eq!(2, tokens.position());
+        assert_eq!(TokenType::OpenParen, tokens.token_at(start).kind);
+        assert_eq!(TokenType::Eof, tokens.token_at(100).kind);
+    }
+
+    #[test]
+    fn should_report_eof_when_expecting_past_end(){
+        let tokens = tokenize("x").unwrap();
+        let mut tokens = TokenStream::new(&tokens);
+        tokens.next();
+        let error = tokens.expect(TokenType::CloseParen).unwrap_err();
+        assert_eq!(TokenType::Eof, error.found);
+        assert_eq!(Span::new(1, 1, 1, 2), error.span);
+        let error = tokens.expect(TokenType::CloseParen).unwrap_err();
+        assert_eq!(TokenType::Eof, error.found);
+    }
+
+    #[test]
+    fn should_keep_the_eof_of_the_source_after_a_multiline_token(){
+        let tokens = tokenize("x = `a\nbc`").unwrap();
+        let mut tokens = TokenStream::new(&tokens);
+        tokens.next();
+        tokens.next();
+        assert_eq!(TokenType::TemplateLiteral, tokens.next().kind);
+        assert_eq!(Span::new(10, 10, 2, 4), tokens.peek().span);
+    }
+
+    #[test]
+    fn should_append_eof_where_the_last_token_ends(){
+        let config = LexerConfig { emit_eof: false, ..LexerConfig::default() };
+        for source in ["let x", "x = `a\nbc`", "\"ab\\\r\n  cd\"", "\"é\" + `\r\n`"] {
+            let lexed: Vec<Token> = Lexer::new(source).with_config(config).collect::<Result<_, _>>().unwrap();
+            let eof = tokenize(source).unwrap().last().unwrap().span;
+            let mut tokens = TokenStream::new(&lexed);
+            while !tokens.at_eof() {
+                tokens.next();
+            }
+            assert_eq!(eof, tokens.peek().span, "{:?}", source);
+        }
+        assert_eq!(Span::new(0, 0, 1, 1), TokenStream::new(&[]).peek().span);
+    }
+
+    #[test]
+    fn should_come_back_to_a_checkpoint(){
+        let tokens = tokenize("let x = 1").unwrap();
+        let mut tokens = TokenStream::new(&tokens);
+        tokens.next();
+        let checkpoint = tokens.checkpoint();
+        tokens.next();
+        tokens.next();
+        assert!(tokens.check(TokenType::Number));
+        tokens.rewind(checkpoint);
+        assert!(tokens.check(TokenType::Identifier));
+        assert_eq!(Some(TokenType::Let), tokens.previous().map(|token| token.kind));
+    }
+
+    #[test]
+    fn should_produce_every_token_once_when_peeking_ahead(){
+        let source = "let x = (1 + 2) * y";
+        let mut tokens = Lookahead::new(Lexer::new(source));
+        let mut produced: Vec<Token> = Vec::new();
+        loop {
+            let ahead = tokens.peek_n(3).cloned();
+            let current = tokens.peek().cloned();
+            let Some(next) = tokens.next() else {
+                break;
+            };
+            let next = next.unwrap();
+            assert_eq!(current.as_ref(), Some(&next));
+            // three places ahead now is two places ahead after the `next`
+            assert_eq!(ahead.as_ref(), tokens.peek_n(2));
+            produced.push(next);
+        }
+        assert_eq!(tokenize(source).unwrap(), produced);
+    }
+
+    #[test]
+    fn should_return_none_when_peeking_past_the_end(){
+        let mut tokens = Lookahead::new(Lexer::new("x"));
+        assert_eq!(TokenType::Eof, tokens.peek_n(1).unwrap().kind);
+        assert_eq!(None, tokens.peek_n(2));
+        assert_eq!(TokenType::Identifier, tokens.next().unwrap().unwrap().kind);
+        assert_eq!(TokenType::Eof, tokens.next().unwrap().unwrap().kind);
+        assert_eq!(None, tokens.peek());
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn should_stop_peeking_at_a_lexing_error(){
+        let mut tokens = Lookahead::new(Lexer::new("a $ b"));
+        assert_eq!(None, tokens.peek_n(1));
+        assert_eq!(None, tokens.peek_n(2));
+        assert_eq!("a", tokens.next().unwrap().unwrap().value);
+        assert!(tokens.next().unwrap().is_err());
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn should_replay_peeked_tokens_after_rewinding(){
+        let source = "let f = (a) + b";
+        let mut tokens = Lookahead::new(Lexer::new(source));
+        tokens.next();
+        tokens.peek_n(2);
+        let checkpoint = tokens.checkpoint();
+        let ahead: Vec<Result<Token, LexError>> = tokens.by_ref().collect();
+        tokens.rewind(checkpoint);
+        assert_eq!(TokenType::Identifier, tokens.peek().unwrap().kind);
+        assert_eq!(ahead, tokens.by_ref().collect::<Vec<_>>());
+        assert_eq!(tokenize(source).unwrap()[1..], ahead.into_iter().collect::<Result<Vec<_>, _>>().unwrap()[..]);
+    }
+}
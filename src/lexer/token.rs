@@ -0,0 +1,1038 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+use crate::lexer::{count_line_breaks, token_value};
+use crate::lexer::intern::Symbol;
+
+#[derive(Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[repr(u8)]
+pub enum TokenType{
+    // literal types
+    Null,
+    Infinity,
+    #[cfg_attr(feature = "serde", serde(rename = "nan"))]
+    NaN,
+    Number,
+    Identifier,
+    StringLiteral,
+    // backtick string, which may span lines
+    TemplateLiteral,
+    // `/pattern/flags`
+    RegexLiteral,
+
+    // an interpolated string is split around its `{...}` expressions:
+    // `"a{x}b{y}c"` is `"a{`, x, `}b{`, y, `}c"`
+    StringStart,
+    StringMiddle,
+    StringEnd,
+
+    // keywords
+    Let,
+    Throw,
+    Try,
+    Catch,
+    Yield,
+    Async,
+    Await,
+    Export,
+    Typeof,
+    Is,
+    As,
+    Const,
+    Defer,
+    With,
+
+    // grouping operators
+    Equals,
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+    OpenBrace,
+    CloseBrace,
+    Comma,
+    BinaryOperator,
+    PlusPlus,
+    MinusMinus,
+    // `!`, logical not
+    Bang,
+    QuestionMark,
+    // `?.`, member access that gives null on a null object
+    SafeNavigation,
+    Colon,
+    // `::` between the segments of a path like `std::math`
+    PathSeparator,
+    Ellipsis,
+    Hash,
+    // `///` comment up to the end of the line, kept for the declaration after it
+    DocComment,
+    // unrecognized characters, only produced by a tolerant lexer
+    Error,
+
+    Eof, // end of file
+}
+
+// every kind, in declaration order
+const ALL_KINDS: [TokenType; 46] = [
+    TokenType::Null,
+    TokenType::Infinity,
+    TokenType::NaN,
+    TokenType::Number,
+    TokenType::Identifier,
+    TokenType::StringLiteral,
+    TokenType::TemplateLiteral,
+    TokenType::RegexLiteral,
+    TokenType::StringStart,
+    TokenType::StringMiddle,
+    TokenType::StringEnd,
+    TokenType::Let,
+    TokenType::Throw,
+    TokenType::Try,
+    TokenType::Catch,
+    TokenType::Yield,
+    TokenType::Async,
+    TokenType::Await,
+    TokenType::Export,
+    TokenType::Typeof,
+    TokenType::Is,
+    TokenType::As,
+    TokenType::Const,
+    TokenType::Defer,
+    TokenType::With,
+    TokenType::Equals,
+    TokenType::OpenParen,
+    TokenType::CloseParen,
+    TokenType::OpenBracket,
+    TokenType::CloseBracket,
+    TokenType::OpenBrace,
+    TokenType::CloseBrace,
+    TokenType::Comma,
+    TokenType::BinaryOperator,
+    TokenType::PlusPlus,
+    TokenType::MinusMinus,
+    TokenType::Bang,
+    TokenType::QuestionMark,
+    TokenType::SafeNavigation,
+    TokenType::Colon,
+    TokenType::PathSeparator,
+    TokenType::Ellipsis,
+    TokenType::Hash,
+    TokenType::DocComment,
+    TokenType::Error,
+    TokenType::Eof,
+];
+
+impl TokenType {
+    // source text shared by every token of this kind, if there is one
+    fn fixed_text(&self) -> Option<&'static str> {
+        match self {
+            TokenType::Null => Some("null"),
+            TokenType::Infinity => Some("Infinity"),
+            TokenType::NaN => Some("NaN"),
+            TokenType::Let => Some("let"),
+            TokenType::Throw => Some("throw"),
+            TokenType::Try => Some("try"),
+            TokenType::Catch => Some("catch"),
+            TokenType::Yield => Some("yield"),
+            TokenType::Async => Some("async"),
+            TokenType::Await => Some("await"),
+            TokenType::Export => Some("export"),
+            TokenType::Typeof => Some("typeof"),
+            TokenType::Is => Some("is"),
+            TokenType::As => Some("as"),
+            TokenType::Const => Some("const"),
+            TokenType::Defer => Some("defer"),
+            TokenType::With => Some("with"),
+            TokenType::Equals => Some("="),
+            TokenType::OpenParen => Some("("),
+            TokenType::CloseParen => Some(")"),
+            TokenType::OpenBracket => Some("["),
+            TokenType::CloseBracket => Some("]"),
+            TokenType::OpenBrace => Some("{"),
+            TokenType::CloseBrace => Some("}"),
+            TokenType::Comma => Some(","),
+            TokenType::PlusPlus => Some("++"),
+            TokenType::MinusMinus => Some("--"),
+            TokenType::Bang => Some("!"),
+            TokenType::QuestionMark => Some("?"),
+            TokenType::SafeNavigation => Some("?."),
+            TokenType::Colon => Some(":"),
+            TokenType::PathSeparator => Some("::"),
+            TokenType::Ellipsis => Some("..."),
+            TokenType::Hash => Some("#"),
+            TokenType::Number
+            | TokenType::Identifier
+            | TokenType::StringLiteral
+            | TokenType::TemplateLiteral
+            | TokenType::RegexLiteral
+            | TokenType::StringStart
+            | TokenType::StringMiddle
+            | TokenType::StringEnd
+            | TokenType::BinaryOperator
+            | TokenType::DocComment
+            | TokenType::Error
+            | TokenType::Eof => None,
+        }
+    }
+
+    /// Every kind, in declaration order.
+    pub fn all() -> &'static [TokenType] {
+        &ALL_KINDS
+    }
+
+    /// Name of the kind in snake case, e.g. `open_paren`, stable enough for
+    /// tooling to key on.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenType::Null => "null",
+            TokenType::Infinity => "infinity",
+            TokenType::NaN => "nan",
+            TokenType::Number => "number",
+            TokenType::Identifier => "identifier",
+            TokenType::StringLiteral => "string_literal",
+            TokenType::TemplateLiteral => "template_literal",
+            TokenType::RegexLiteral => "regex_literal",
+            TokenType::StringStart => "string_start",
+            TokenType::StringMiddle => "string_middle",
+            TokenType::StringEnd => "string_end",
+            TokenType::Let => "let",
+            TokenType::Throw => "throw",
+            TokenType::Try => "try",
+            TokenType::Catch => "catch",
+            TokenType::Yield => "yield",
+            TokenType::Async => "async",
+            TokenType::Await => "await",
+            TokenType::Export => "export",
+            TokenType::Typeof => "typeof",
+            TokenType::Is => "is",
+            TokenType::As => "as",
+            TokenType::Const => "const",
+            TokenType::Defer => "defer",
+            TokenType::With => "with",
+            TokenType::Equals => "equals",
+            TokenType::OpenParen => "open_paren",
+            TokenType::CloseParen => "close_paren",
+            TokenType::OpenBracket => "open_bracket",
+            TokenType::CloseBracket => "close_bracket",
+            TokenType::OpenBrace => "open_brace",
+            TokenType::CloseBrace => "close_brace",
+            TokenType::Comma => "comma",
+            TokenType::BinaryOperator => "binary_operator",
+            TokenType::PlusPlus => "plus_plus",
+            TokenType::MinusMinus => "minus_minus",
+            TokenType::Bang => "bang",
+            TokenType::QuestionMark => "question_mark",
+            TokenType::SafeNavigation => "safe_navigation",
+            TokenType::Colon => "colon",
+            TokenType::PathSeparator => "path_separator",
+            TokenType::Ellipsis => "ellipsis",
+            TokenType::Hash => "hash",
+            TokenType::DocComment => "doc_comment",
+            TokenType::Error => "error",
+            TokenType::Eof => "eof",
+        }
+    }
+
+    // the predicates below list every kind on purpose, so a new kind has to be
+    // classified before it compiles
+
+    /// Kinds that are a complete value on their own, like `45` or `null`.
+    pub fn is_literal(&self) -> bool {
+        match self {
+            TokenType::Null
+            | TokenType::Infinity
+            | TokenType::NaN
+            | TokenType::Number
+            | TokenType::StringLiteral
+            | TokenType::TemplateLiteral
+            | TokenType::RegexLiteral => true,
+            TokenType::Identifier
+            | TokenType::StringStart
+            | TokenType::StringMiddle
+            | TokenType::StringEnd
+            | TokenType::Let
+            | TokenType::Throw
+            | TokenType::Try
+            | TokenType::Catch
+            | TokenType::Yield
+            | TokenType::Async
+            | TokenType::Await
+            | TokenType::Export
+            | TokenType::Typeof
+            | TokenType::Is
+            | TokenType::As
+            | TokenType::Const
+            | TokenType::Defer
+            | TokenType::With
+            | TokenType::Equals
+            | TokenType::OpenParen
+            | TokenType::CloseParen
+            | TokenType::OpenBracket
+            | TokenType::CloseBracket
+            | TokenType::OpenBrace
+            | TokenType::CloseBrace
+            | TokenType::Comma
+            | TokenType::BinaryOperator
+            | TokenType::PlusPlus
+            | TokenType::MinusMinus
+            | TokenType::Bang
+            | TokenType::QuestionMark
+            | TokenType::SafeNavigation
+            | TokenType::Colon
+            | TokenType::PathSeparator
+            | TokenType::Ellipsis
+            | TokenType::Hash
+            | TokenType::DocComment
+            | TokenType::Error
+            | TokenType::Eof => false,
+        }
+    }
+
+    /// Kinds spelled with a reserved word, see [`KEYWORDS`].
+    pub fn is_keyword(&self) -> bool {
+        match self {
+            TokenType::Null
+            | TokenType::Infinity
+            | TokenType::NaN
+            | TokenType::Let
+            | TokenType::Throw
+            | TokenType::Try
+            | TokenType::Catch
+            | TokenType::Yield
+            | TokenType::Async
+            | TokenType::Await
+            | TokenType::Export
+            | TokenType::Typeof
+            | TokenType::Is
+            | TokenType::As
+            | TokenType::Const
+            | TokenType::Defer
+            | TokenType::With => true,
+            TokenType::Number
+            | TokenType::Identifier
+            | TokenType::StringLiteral
+            | TokenType::TemplateLiteral
+            | TokenType::RegexLiteral
+            | TokenType::StringStart
+            | TokenType::StringMiddle
+            | TokenType::StringEnd
+            | TokenType::Equals
+            | TokenType::OpenParen
+            | TokenType::CloseParen
+            | TokenType::OpenBracket
+            | TokenType::CloseBracket
+            | TokenType::OpenBrace
+            | TokenType::CloseBrace
+            | TokenType::Comma
+            | TokenType::BinaryOperator
+            | TokenType::PlusPlus
+            | TokenType::MinusMinus
+            | TokenType::Bang
+            | TokenType::QuestionMark
+            | TokenType::SafeNavigation
+            | TokenType::Colon
+            | TokenType::PathSeparator
+            | TokenType::Ellipsis
+            | TokenType::Hash
+            | TokenType::DocComment
+            | TokenType::Error
+            | TokenType::Eof => false,
+        }
+    }
+
+    pub fn is_operator(&self) -> bool {
+        match self {
+            TokenType::Equals
+            | TokenType::BinaryOperator
+            | TokenType::PlusPlus
+            | TokenType::MinusMinus
+            | TokenType::Bang
+            | TokenType::QuestionMark
+            | TokenType::SafeNavigation
+            | TokenType::Colon
+            | TokenType::PathSeparator
+            | TokenType::Ellipsis => true,
+            TokenType::Null
+            | TokenType::Infinity
+            | TokenType::NaN
+            | TokenType::Number
+            | TokenType::Identifier
+            | TokenType::StringLiteral
+            | TokenType::TemplateLiteral
+            | TokenType::RegexLiteral
+            | TokenType::StringStart
+            | TokenType::StringMiddle
+            | TokenType::StringEnd
+            | TokenType::Let
+            | TokenType::Throw
+            | TokenType::Try
+            | TokenType::Catch
+            | TokenType::Yield
+            | TokenType::Async
+            | TokenType::Await
+            | TokenType::Export
+            | TokenType::Typeof
+            | TokenType::Is
+            | TokenType::As
+            | TokenType::Const
+            | TokenType::Defer
+            | TokenType::With
+            | TokenType::OpenParen
+            | TokenType::CloseParen
+            | TokenType::OpenBracket
+            | TokenType::CloseBracket
+            | TokenType::OpenBrace
+            | TokenType::CloseBrace
+            | TokenType::Comma
+            | TokenType::Hash
+            | TokenType::DocComment
+            | TokenType::Error
+            | TokenType::Eof => false,
+        }
+    }
+
+    // every delimiter has a match in `matching_delimiter`, which is exhaustive
+    pub fn is_opening_delimiter(&self) -> bool {
+        matches!(self, TokenType::OpenParen | TokenType::OpenBracket | TokenType::OpenBrace)
+    }
+
+    pub fn is_closing_delimiter(&self) -> bool {
+        matches!(self, TokenType::CloseParen | TokenType::CloseBracket | TokenType::CloseBrace)
+    }
+
+    /// The other half of a delimiter pair, `(` for `)` and the other way round.
+    pub fn matching_delimiter(&self) -> Option<TokenType> {
+        match self {
+            TokenType::OpenParen => Some(TokenType::CloseParen),
+            TokenType::CloseParen => Some(TokenType::OpenParen),
+            TokenType::OpenBracket => Some(TokenType::CloseBracket),
+            TokenType::CloseBracket => Some(TokenType::OpenBracket),
+            TokenType::OpenBrace => Some(TokenType::CloseBrace),
+            TokenType::CloseBrace => Some(TokenType::OpenBrace),
+            TokenType::Null
+            | TokenType::Infinity
+            | TokenType::NaN
+            | TokenType::Number
+            | TokenType::Identifier
+            | TokenType::StringLiteral
+            | TokenType::TemplateLiteral
+            | TokenType::RegexLiteral
+            | TokenType::StringStart
+            | TokenType::StringMiddle
+            | TokenType::StringEnd
+            | TokenType::Let
+            | TokenType::Throw
+            | TokenType::Try
+            | TokenType::Catch
+            | TokenType::Yield
+            | TokenType::Async
+            | TokenType::Await
+            | TokenType::Export
+            | TokenType::Typeof
+            | TokenType::Is
+            | TokenType::As
+            | TokenType::Const
+            | TokenType::Defer
+            | TokenType::With
+            | TokenType::Comma
+            | TokenType::Equals
+            | TokenType::BinaryOperator
+            | TokenType::PlusPlus
+            | TokenType::MinusMinus
+            | TokenType::Bang
+            | TokenType::QuestionMark
+            | TokenType::SafeNavigation
+            | TokenType::Colon
+            | TokenType::PathSeparator
+            | TokenType::Ellipsis
+            | TokenType::Hash
+            | TokenType::DocComment
+            | TokenType::Error
+            | TokenType::Eof => None,
+        }
+    }
+}
+
+// `KEYWORDS` and `keyword_kind` are generated from one table, so the lexer
+// can't recognize a word the list doesn't have
+macro_rules! keywords {
+    ($($word:literal => $kind:ident,)*) => {
+        /// Every keyword and the kind it is lexed as, the table [`keyword_kind`]
+        /// looks words up in.
+        pub const KEYWORDS: [(&str, TokenType); [$($word),*].len()] = [$(($word, TokenType::$kind)),*];
+
+        /// Kind of the keyword `word`, or `None` if it is an ordinary identifier.
+        pub fn keyword_kind(word: &str) -> Option<TokenType> {
+            match word {
+                $($word => Some(TokenType::$kind),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+keywords! {
+    "let" => Let,
+    "null" => Null,
+    "Infinity" => Infinity,
+    "NaN" => NaN,
+    "throw" => Throw,
+    "try" => Try,
+    "catch" => Catch,
+    "yield" => Yield,
+    "async" => Async,
+    "await" => Await,
+    "export" => Export,
+    "typeof" => Typeof,
+    "is" => Is,
+    "as" => As,
+    "const" => Const,
+    "defer" => Defer,
+    "with" => With,
+}
+
+/// Every keyword and the kind it is lexed as, see [`KEYWORDS`].
+pub fn keywords() -> &'static [(&'static str, TokenType)] {
+    &KEYWORDS
+}
+
+/// User-facing name of the kind, e.g. `number` or `'('`.
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenType::Number => write!(f, "number"),
+            TokenType::Identifier => write!(f, "identifier"),
+            TokenType::StringLiteral => write!(f, "string"),
+            TokenType::TemplateLiteral => write!(f, "template string"),
+            TokenType::RegexLiteral => write!(f, "regex"),
+            TokenType::StringStart | TokenType::StringMiddle | TokenType::StringEnd => write!(f, "interpolated string"),
+            TokenType::BinaryOperator => write!(f, "operator"),
+            TokenType::DocComment => write!(f, "doc comment"),
+            TokenType::Error => write!(f, "unrecognized input"),
+            TokenType::Eof => write!(f, "end of file"),
+            // kinds always spelled the same way are quoted as written
+            TokenType::Null
+            | TokenType::Infinity
+            | TokenType::NaN
+            | TokenType::Let
+            | TokenType::Throw
+            | TokenType::Try
+            | TokenType::Catch
+            | TokenType::Yield
+            | TokenType::Async
+            | TokenType::Await
+            | TokenType::Export
+            | TokenType::Typeof
+            | TokenType::Is
+            | TokenType::As
+            | TokenType::Const
+            | TokenType::Defer
+            | TokenType::With
+            | TokenType::Equals
+            | TokenType::OpenParen
+            | TokenType::CloseParen
+            | TokenType::OpenBracket
+            | TokenType::CloseBracket
+            | TokenType::OpenBrace
+            | TokenType::CloseBrace
+            | TokenType::Comma
+            | TokenType::PlusPlus
+            | TokenType::MinusMinus
+            | TokenType::Bang
+            | TokenType::QuestionMark
+            | TokenType::SafeNavigation
+            | TokenType::Colon
+            | TokenType::PathSeparator
+            | TokenType::Ellipsis
+            | TokenType::Hash => write!(f, "'{}'", self.fixed_text().unwrap_or_default()),
+        }
+    }
+}
+
+/// Location of a token in the source code.
+///
+/// `start` and `end` are byte offsets (`end` is exclusive), `line` and `column`
+/// point at the first character of the token and are both 1-based. Columns are
+/// counted in characters, not bytes, unless the lexer was given a different
+/// [`LexerConfig`](crate::lexer::LexerConfig).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: u32, column: u32) -> Self {
+        Span { start, end, line, column }
+    }
+
+    /// The text of `source` this span covers. `source` must be the text the
+    /// span was produced from.
+    pub fn slice<'a>(&self, source: &'a str) -> &'a str {
+        debug_assert!(
+            source.is_char_boundary(self.start) && source.is_char_boundary(self.end),
+            "{:?} does not fall on char boundaries of the source",
+            self
+        );
+        &source[self.start..self.end]
+    }
+
+    /// Column of the span start counted in UTF-16 code units, as LSP clients
+    /// expect, whatever unit the lexer used. `source` must be the text the span
+    /// was produced from.
+    pub fn utf16_column(&self, source: &str) -> u32 {
+        let before = &source[..self.start];
+        let line_start = before.rfind(['\n', '\r']).map_or(0, |i| i + 1);
+        before[line_start..].encode_utf16().count() as u32 + 1
+    }
+
+    /// Smallest span covering both `a` and `b`, in either order. Line and column
+    /// are the ones of the span that starts first.
+    pub fn merge(a: Span, b: Span) -> Span {
+        let (first, second) = if a.start <= b.start { (a, b) } else { (b, a) };
+        Span { end: first.end.max(second.end), ..first }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TriviaKind {
+    // run of whitespace other than newlines
+    Whitespace,
+    Newline,
+    // `//` up to, not including, the end of the line
+    LineComment,
+}
+
+/// Source text between tokens that doesn't affect meaning.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trivia<'src> {
+    pub kind: TriviaKind,
+    pub text: Cow<'src, str>,
+    pub span: Span,
+}
+
+impl<'src> Trivia<'src> {
+    pub fn new(text: impl Into<Cow<'src, str>>, kind: TriviaKind, span: Span) -> Self {
+        Trivia { kind, text: text.into(), span }
+    }
+
+    pub fn to_owned(&self) -> Trivia<'static> {
+        Trivia::new(self.text.clone().into_owned(), self.kind, self.span)
+    }
+}
+
+/// A single token.
+///
+/// Tokens produced by the lexer borrow their text straight from the source, so
+/// lexing doesn't allocate per token. Use [`Token::to_owned`] to detach a token
+/// from the source it was lexed from.
+///
+/// Trivia is only filled in when lexing with [`Lexer::with_trivia`](crate::lexer::Lexer::with_trivia). Trailing
+/// trivia runs up to and including the first newline after the token, anything
+/// after that is leading trivia of the next token.
+///
+/// The value of a string continued with `\` drops the continuations, such a
+/// token also keeps its raw source text, see [`Token::raw_text`].
+///
+/// Identifier and keyword tokens lexed through a [`Session`] also carry a
+/// [`Symbol`]; when both tokens have one, equality compares the symbols instead
+/// of the text. Only compare tokens lexed by the same session.
+///
+/// [`Session`]: crate::lexer::intern::Session
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token<'src> {
+    pub(crate) value: Cow<'src, str>,
+    pub(crate) kind: TokenType,
+    pub(crate) span: Span,
+    // source text, only kept when it differs from `value`; boxed since the
+    // few tokens that have one allocate their value anyway
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub(crate) raw: Option<Box<str>>,
+    pub(crate) leading_trivia: Vec<Trivia<'src>>,
+    pub(crate) trailing_trivia: Vec<Trivia<'src>>,
+    // symbols only mean something next to their interner
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) symbol: Option<Symbol>,
+}
+
+impl<'src> Token<'src> {
+    pub fn new(value: impl Into<Cow<'src, str>>, kind: TokenType, span: Span) -> Self {
+        Token {
+            value: value.into(),
+            kind,
+            span,
+            raw: None,
+            leading_trivia: Vec::new(),
+            trailing_trivia: Vec::new(),
+            symbol: None,
+        }
+    }
+
+    // token scanned from `text`, which is kept as the raw text when the value
+    // differs from it
+    pub(crate) fn from_source_text(text: &'src str, kind: TokenType, span: Span) -> Self {
+        let value = token_value(kind, text);
+        let raw = matches!(value, Cow::Owned(_)).then(|| Box::from(text));
+        Token { raw, ..Token::new(value, kind, span) }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// The token exactly as it was written in the source. Only strings
+    /// continued with `\` have a raw text different from their value.
+    pub fn raw_text(&self) -> &str {
+        self.raw.as_deref().unwrap_or(&self.value)
+    }
+
+    pub fn kind(&self) -> TokenType {
+        self.kind
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The source text the token was scanned from, see [`Span::slice`].
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        self.span.slice(source)
+    }
+
+    pub fn leading_trivia(&self) -> &[Trivia<'src>] {
+        &self.leading_trivia
+    }
+
+    pub fn trailing_trivia(&self) -> &[Trivia<'src>] {
+        &self.trailing_trivia
+    }
+
+    /// The interned text of an identifier or keyword lexed through a
+    /// [`Session`](crate::lexer::intern::Session), `None` otherwise.
+    pub fn symbol(&self) -> Option<Symbol> {
+        self.symbol
+    }
+
+    fn same_text(&self, other: &Token<'_>) -> bool {
+        match (self.symbol, other.symbol) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.value == other.value,
+        }
+    }
+
+    /// Compares kind and text only, two tokens at different places in the
+    /// source are still the same.
+    pub fn same_kind_and_text(&self, other: &Token<'_>) -> bool {
+        self.kind == other.kind && self.same_text(other)
+    }
+
+    /// Pattern and flags of a regex literal, `None` for any other token.
+    pub fn regex_parts(&self) -> Option<(&str, &str)> {
+        if self.kind != TokenType::RegexLiteral {
+            return None;
+        }
+        let end = self.value.rfind('/')?;
+        Some((&self.value[1..end], &self.value[end + 1..]))
+    }
+
+    /// Copies the token text so the token no longer borrows the source.
+    pub fn to_owned(&self) -> Token<'static> {
+        Token {
+            value: Cow::Owned(self.value.clone().into_owned()),
+            kind: self.kind,
+            span: self.span,
+            raw: self.raw.clone(),
+            leading_trivia: self.leading_trivia.iter().map(Trivia::to_owned).collect(),
+            trailing_trivia: self.trailing_trivia.iter().map(Trivia::to_owned).collect(),
+            symbol: self.symbol,
+        }
+    }
+}
+
+impl PartialEq for Token<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.span == other.span
+            && self.same_text(other)
+            && self.leading_trivia == other.leading_trivia
+            && self.trailing_trivia == other.trailing_trivia
+    }
+}
+
+// leaves the text out, equal tokens may have different text when they come
+// from different sessions
+impl Hash for Token<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kind.hash(state);
+        self.span.hash(state);
+        self.leading_trivia.hash(state);
+        self.trailing_trivia.hash(state);
+    }
+}
+
+/// User-facing description of the token, e.g. `` `45` (number) `` or `'let'`.
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            TokenType::Eof => write!(f, "{}", self.kind),
+            kind if kind.fixed_text().is_some() => write!(f, "{}", kind),
+            kind => write!(f, "`{}` ({})", self.value, kind),
+        }
+    }
+}
+
+/// Compares two token sequences by kind and text, ignoring where the tokens are.
+pub fn tokens_eq_ignoring_spans(a: &[Token<'_>], b: &[Token<'_>]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.same_kind_and_text(b))
+}
+
+/// The run of `tokens` that touch `line`, EOF included when it sits on that
+/// line. Tokens spanning several lines, like template strings, are part of the
+/// run of every line they touch, which is why the `source` the tokens were
+/// lexed from is needed. `tokens` must be in source order.
+pub fn tokens_on_line<'t, 'src>(tokens: &'t [Token<'src>], source: &str, line: u32) -> &'t [Token<'src>] {
+    let last_line = |token: &Token| token.span.line + count_line_breaks(token.span.slice(source));
+    let start = tokens.partition_point(|token| last_line(token) < line);
+    let end = start + tokens[start..].partition_point(|token| token.span.line <= line);
+    &tokens[start..end]
+}
+
+#[cfg(test)]
+mod tests{
+    use std::collections::BTreeSet;
+
+    use crate::lexer::{tokenize, tokenize_preserving_trivia};
+    use crate::lexer::token::{keyword_kind, tokens_eq_ignoring_spans, tokens_on_line, Span, Token, TokenType, KEYWORDS};
+
+    fn values<'a>(tokens: &'a [Token]) -> Vec<&'a str> {
+        tokens.iter().map(Token::value).collect()
+    }
+
+    #[test]
+    fn should_find_tokens_by_line(){
+        let source = "let a = 1\n\n  // note\nlet b = a\nb";
+        let tokens = tokenize(source).unwrap();
+        assert_eq!(vec!["let", "a", "=", "1"], values(tokens_on_line(&tokens, source, 1)));
+        assert!(tokens_on_line(&tokens, source, 2).is_empty());
+        assert!(tokens_on_line(&tokens, source, 3).is_empty());
+        assert_eq!(vec!["let", "b", "=", "a"], values(tokens_on_line(&tokens, source, 4)));
+        // the last line also holds EOF
+        assert_eq!(vec!["b", ""], values(tokens_on_line(&tokens, source, 5)));
+        assert!(tokens_on_line(&tokens, source, 6).is_empty());
+        assert!(tokens_on_line(&[], "", 1).is_empty());
+    }
+
+    #[test]
+    fn should_find_multi_line_tokens_on_every_line_they_touch(){
+        let source = "let a = `x\n\ny` + \"p\\\n q\"\nb";
+        let tokens = tokenize(source).unwrap();
+        assert_eq!(vec!["let", "a", "=", "`x\n\ny`"], values(tokens_on_line(&tokens, source, 1)));
+        assert_eq!(vec!["`x\n\ny`"], values(tokens_on_line(&tokens, source, 2)));
+        assert_eq!(vec!["`x\n\ny`", "+", "\"pq\""], values(tokens_on_line(&tokens, source, 3)));
+        assert_eq!(vec!["\"pq\""], values(tokens_on_line(&tokens, source, 4)));
+        assert_eq!(vec!["b", ""], values(tokens_on_line(&tokens, source, 5)));
+    }
+
+    #[test]
+    fn should_find_no_tokens_on_a_comment_line_with_trivia(){
+        let source = "let a\n// only a comment\nlet b";
+        let tokens = tokenize_preserving_trivia(source).unwrap();
+        assert!(tokens_on_line(&tokens, source, 2).is_empty());
+        // the comment is kept as trivia of the token on the next line
+        let next_line = tokens_on_line(&tokens, source, 3);
+        assert_eq!("// only a comment", next_line[0].leading_trivia()[0].text);
+    }
+
+    #[test]
+    fn should_classify_every_kind(){
+        // kind, literal, keyword, operator, opening, closing
+        let table = [
+            (TokenType::Null, true, true, false, false, false),
+            (TokenType::Infinity, true, true, false, false, false),
+            (TokenType::NaN, true, true, false, false, false),
+            (TokenType::Number, true, false, false, false, false),
+            (TokenType::Identifier, false, false, false, false, false),
+            (TokenType::StringLiteral, true, false, false, false, false),
+            (TokenType::TemplateLiteral, true, false, false, false, false),
+            (TokenType::RegexLiteral, true, false, false, false, false),
+            (TokenType::StringStart, false, false, false, false, false),
+            (TokenType::StringMiddle, false, false, false, false, false),
+            (TokenType::StringEnd, false, false, false, false, false),
+            (TokenType::Let, false, true, false, false, false),
+            (TokenType::Throw, false, true, false, false, false),
+            (TokenType::Try, false, true, false, false, false),
+            (TokenType::Catch, false, true, false, false, false),
+            (TokenType::Yield, false, true, false, false, false),
+            (TokenType::Async, false, true, false, false, false),
+            (TokenType::Await, false, true, false, false, false),
+            (TokenType::Export, false, true, false, false, false),
+            (TokenType::Typeof, false, true, false, false, false),
+            (TokenType::Is, false, true, false, false, false),
+            (TokenType::As, false, true, false, false, false),
+            (TokenType::Const, false, true, false, false, false),
+            (TokenType::Defer, false, true, false, false, false),
+            (TokenType::With, false, true, false, false, false),
+            (TokenType::Equals, false, false, true, false, false),
+            (TokenType::OpenParen, false, false, false, true, false),
+            (TokenType::CloseParen, false, false, false, false, true),
+            (TokenType::OpenBracket, false, false, false, true, false),
+            (TokenType::CloseBracket, false, false, false, false, true),
+            (TokenType::OpenBrace, false, false, false, true, false),
+            (TokenType::CloseBrace, false, false, false, false, true),
+            (TokenType::Comma, false, false, false, false, false),
+            (TokenType::BinaryOperator, false, false, true, false, false),
+            (TokenType::PlusPlus, false, false, true, false, false),
+            (TokenType::MinusMinus, false, false, true, false, false),
+            (TokenType::Bang, false, false, true, false, false),
+            (TokenType::QuestionMark, false, false, true, false, false),
+            (TokenType::SafeNavigation, false, false, true, false, false),
+            (TokenType::Colon, false, false, true, false, false),
+            (TokenType::PathSeparator, false, false, true, false, false),
+            (TokenType::Ellipsis, false, false, true, false, false),
+            (TokenType::Hash, false, false, false, false, false),
+            (TokenType::DocComment, false, false, false, false, false),
+            (TokenType::Error, false, false, false, false, false),
+            (TokenType::Eof, false, false, false, false, false),
+        ];
+        for (kind, literal, keyword, operator, opening, closing) in table {
+            assert_eq!(
+                (literal, keyword, operator, opening, closing),
+                (kind.is_literal(), kind.is_keyword(), kind.is_operator(), kind.is_opening_delimiter(), kind.is_closing_delimiter()),
+                "{:?}",
+                kind
+            );
+            assert_eq!(opening || closing, kind.matching_delimiter().is_some(), "{:?}", kind);
+            if let Some(other) = kind.matching_delimiter() {
+                assert_eq!(Some(kind), other.matching_delimiter());
+                assert_eq!(opening, other.is_closing_delimiter());
+            }
+            assert_eq!(keyword, KEYWORDS.iter().any(|&(_, k)| k == kind), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn should_look_up_every_keyword(){
+        for (word, kind) in KEYWORDS {
+            assert_eq!(Some(kind), keyword_kind(word));
+            assert_eq!(Some(word), kind.fixed_text());
+        }
+        assert_eq!(None, keyword_kind("lets"));
+        assert_eq!(None, keyword_kind("Let"));
+    }
+
+    #[test]
+    fn should_list_every_kind_once_in_declaration_order(){
+        let kinds = TokenType::all();
+        // `Eof` is declared last
+        assert_eq!(TokenType::Eof as usize + 1, kinds.len());
+        for (index, kind) in kinds.iter().enumerate() {
+            assert_eq!(index, *kind as usize, "{:?}", kind);
+        }
+        let names: BTreeSet<&str> = kinds.iter().map(TokenType::name).collect();
+        assert_eq!(kinds.len(), names.len());
+        assert_eq!(("open_paren", "nan", "eof"), (TokenType::OpenParen.name(), TokenType::NaN.name(), TokenType::Eof.name()));
+    }
+
+    #[test]
+    fn should_slice_multibyte_source_by_span(){
+        let source = "let é = \"日本\"";
+        let tokens = tokenize(source).unwrap();
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text(source)).collect();
+        assert_eq!(vec!["let", "é", "=", "\"日本\"", ""], texts);
+        assert_eq!("é = ", Span::new(4, 9, 1, 5).slice(source));
+    }
+
+    #[test]
+    fn should_merge_spans_across_lines(){
+        let source = "let a = 1\nlet b = a";
+        let tokens = tokenize(source).unwrap();
+        let (first, last) = (tokens[1].span(), tokens[7].span());
+        let merged = Span::merge(last, first);
+        assert_eq!(Span::new(4, 19, 1, 5), merged);
+        assert_eq!(merged, Span::merge(first, last));
+        assert_eq!("a = 1\nlet b = a", merged.slice(source));
+    }
+
+    #[test]
+    fn should_compare_tokens_with_and_without_spans(){
+        let first = Token::new("x", TokenType::Identifier, Span::new(0, 1, 1, 1));
+        let moved = Token::new("x", TokenType::Identifier, Span::new(4, 5, 2, 1));
+        let other = Token::new("y", TokenType::Identifier, Span::new(0, 1, 1, 1));
+        assert_eq!(first, first.clone());
+        assert_ne!(first, moved);
+        assert!(first.same_kind_and_text(&moved));
+        assert!(!first.same_kind_and_text(&other));
+        assert!(!tokens_eq_ignoring_spans(&[first.clone(), moved], &[first]));
+    }
+
+    #[test]
+    fn should_display_every_kind_of_token(){
+        let source = r#"null Infinity NaN 45 x "s" `t` "a{b}c{d}e" let throw try catch yield async await export typeof is as const defer with = /a+/g ( ) [ ] { } , + ++ -- ! ? ?. : :: ... # /// doc"#;
+        let tokens = tokenize(source).unwrap();
+        let actual: Vec<(String, String)> = tokens.iter().map(|t| (t.kind.to_string(), t.to_string())).collect();
+        let expected = vec![
+            ("'null'", "'null'"),
+            ("'Infinity'", "'Infinity'"),
+            ("'NaN'", "'NaN'"),
+            ("number", "`45` (number)"),
+            ("identifier", "`x` (identifier)"),
+            ("string", "`\"s\"` (string)"),
+            ("template string", "``t`` (template string)"),
+            ("interpolated string", "`\"a{` (interpolated string)"),
+            ("identifier", "`b` (identifier)"),
+            ("interpolated string", "`}c{` (interpolated string)"),
+            ("identifier", "`d` (identifier)"),
+            ("interpolated string", "`}e\"` (interpolated string)"),
+            ("'let'", "'let'"),
+            ("'throw'", "'throw'"),
+            ("'try'", "'try'"),
+            ("'catch'", "'catch'"),
+            ("'yield'", "'yield'"),
+            ("'async'", "'async'"),
+            ("'await'", "'await'"),
+            ("'export'", "'export'"),
+            ("'typeof'", "'typeof'"),
+            ("'is'", "'is'"),
+            ("'as'", "'as'"),
+            ("'const'", "'const'"),
+            ("'defer'", "'defer'"),
+            ("'with'", "'with'"),
+            ("'='", "'='"),
+            ("regex", "`/a+/g` (regex)"),
+            ("'('", "'('"),
+            ("')'", "')'"),
+            ("'['", "'['"),
+            ("']'", "']'"),
+            ("'{'", "'{'"),
+            ("'}'", "'}'"),
+            ("','", "','"),
+            ("operator", "`+` (operator)"),
+            ("'++'", "'++'"),
+            ("'--'", "'--'"),
+            ("'!'", "'!'"),
+            ("'?'", "'?'"),
+            ("'?.'", "'?.'"),
+            ("':'", "':'"),
+            ("'::'", "'::'"),
+            ("'...'", "'...'"),
+            ("'#'", "'#'"),
+            ("doc comment", "`/// doc` (doc comment)"),
+            ("end of file", "end of file"),
+        ];
+        let expected: Vec<(String, String)> = expected.into_iter().map(|(k, t)| (k.to_string(), t.to_string())).collect();
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,7 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod ast;
+pub mod lexer;
+pub mod parser;
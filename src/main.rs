@@ -1,9 +1,32 @@
+use std::env;
+use std::io;
+use std::path::Path;
+use std::process;
 
-use crate::lexer::lexer::tokenize;
-
-mod lexer;
+use lexer_analyzer::lexer::{iter_tokens, read_file, read_source, LexError};
 
+// tokens are printed one per line as they are scanned, so a long file shows
+// output right away and everything up to the first error is still printed;
+// without a path, or with `-`, the source is read from stdin
 fn main() {
-    let source_code = "let x = 45 * (4 / 3)";
-    println!("result: \n {:?}", tokenize(source_code));
+    let path = env::args().nth(1).filter(|path| path != "-");
+    let source_code = match &path {
+        Some(path) => read_file(Path::new(path)),
+        None => read_source(io::stdin().lock()),
+    };
+    let source_code = source_code.unwrap_or_else(|err| fail(&err));
+    for result in iter_tokens(&source_code) {
+        match result {
+            Ok(token) => println!("{}:{} {}", token.span().line, token.span().column, token),
+            Err(err) => match &path {
+                Some(path) => fail(&err.in_file(Path::new(path))),
+                None => fail(&err),
+            },
+        }
+    }
+}
+
+fn fail(err: &LexError) -> ! {
+    eprintln!("error: {}", err);
+    process::exit(1);
 }
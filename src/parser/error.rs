@@ -0,0 +1,80 @@
+use core::fmt;
+
+use crate::lexer::stream::TokenError;
+use crate::lexer::{Span, TokenType};
+
+/// What the parser was looking for when it failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expected {
+    Token(TokenType),
+    /// Anything that starts an expression, like a number or `(`.
+    Expression,
+    /// A name, `[` or `{`, what a `let` binds to.
+    Pattern,
+    /// The `= value` a `const` can't go without.
+    ConstantValue,
+    /// Something a value can be assigned to, like a name.
+    AssignmentTarget,
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Token(kind) => write!(f, "{}", kind),
+            Expected::Expression => write!(f, "expression"),
+            Expected::Pattern => write!(f, "pattern"),
+            Expected::ConstantValue => write!(f, "'=' and a value"),
+            Expected::AssignmentTarget => write!(f, "assignment target"),
+        }
+    }
+}
+
+/// Error produced when the tokens don't match what the parser expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Span of the token that was found.
+    pub span: Span,
+    pub found: TokenType,
+    pub expected: Expected,
+    /// Where the parser was, like `after expression`, see
+    /// [`ParseError::with_context`].
+    pub context: Option<&'static str>,
+    /// Span of the `{` of the block EOF was found in, when it is missing its
+    /// `}`.
+    pub unclosed: Option<Span>,
+}
+
+impl ParseError {
+    /// Says where the parser was when it failed, the context reads right after
+    /// what was expected: `expected ')' after expression`.
+    pub fn with_context(mut self, context: &'static str) -> Self {
+        self.context = Some(context);
+        self
+    }
+}
+
+/// `expected ')' after expression, found 'let' at 1:14`, without the context
+/// when there is none. An unclosed block adds where it started:
+/// `expected '}', found end of file at 3:1, unclosed block started at 2:1`.
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}", self.expected)?;
+        if let Some(context) = self.context {
+            write!(f, " {}", context)?;
+        }
+        write!(f, ", found {} at {}:{}", self.found, self.span.line, self.span.column)?;
+        if let Some(open) = self.unclosed {
+            write!(f, ", unclosed block started at {}:{}", open.line, open.column)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+impl From<TokenError> for ParseError {
+    fn from(error: TokenError) -> Self {
+        ParseError { span: error.span, found: error.found, expected: Expected::Token(error.expected), context: None, unclosed: None }
+    }
+}
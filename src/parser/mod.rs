@@ -0,0 +1,1168 @@
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::ast::{BinaryOp, Block, Expr, FieldPattern, Pattern, Program, Spanned, Stmt, UnaryOp};
+use crate::lexer::stream::TokenStream;
+use crate::lexer::{Span, Token, TokenType};
+
+mod error;
+
+pub use error::{Expected, ParseError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    /// `a - b - c` is `(a - b) - c`.
+    Left,
+    /// `a ** b ** c` is `a ** (b ** c)`.
+    Right,
+}
+
+/// How an infix operator binds, a higher power binds tighter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+    pub op: BinaryOp,
+    pub power: u8,
+    pub assoc: Assoc,
+}
+
+/// Infix operators the parser knows, by the text of the `BinaryOperator`
+/// token spelling them. A new operator needs nothing but an entry here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperatorTable {
+    infix: Vec<(&'static str, Binding)>,
+}
+
+// `-` and `!` bind tighter than any binary operator, `-2 ** 2` is `(-2) ** 2`
+// and `!x == y` is `(!x) == y`
+const PREFIX_POWER: u8 = 55;
+
+// `as` binds tighter than `*` but looser than `**` and the prefix operators,
+// `-x as T` is `(-x) as T` and `a * b as T` is `a * (b as T)`
+const CAST_POWER: u8 = 35;
+
+// `=` binds looser than any operator and groups to the right, `a = b = 1 + 2`
+// is `a = (b = (1 + 2))`
+const ASSIGN_POWER: u8 = 1;
+
+// `?.` binds tighter than any operator, prefix ones included, `-a?.b` is
+// `-(a?.b)`
+const POSTFIX_POWER: u8 = 60;
+
+// the parser recurses into nested expressions, deeper input is an error
+// rather than a stack overflow
+const MAX_NESTING: usize = 256;
+
+impl OperatorTable {
+    /// A table without any operator, only operands parse.
+    pub fn empty() -> Self {
+        OperatorTable { infix: Vec::new() }
+    }
+
+    /// The operators of the language: `??`, then `==`, then `+` and `-`, then
+    /// `*`, `/` and `%`, then the right-associative `**`.
+    pub fn standard() -> Self {
+        OperatorTable::empty()
+            .infix("??", BinaryOp::Coalesce, 5, Assoc::Left)
+            .infix("==", BinaryOp::Eq, 10, Assoc::Left)
+            .infix("+", BinaryOp::Add, 20, Assoc::Left)
+            .infix("-", BinaryOp::Sub, 20, Assoc::Left)
+            .infix("*", BinaryOp::Mul, 30, Assoc::Left)
+            .infix("/", BinaryOp::Div, 30, Assoc::Left)
+            .infix("%", BinaryOp::Mod, 30, Assoc::Left)
+            .infix("**", BinaryOp::Pow, 50, Assoc::Right)
+    }
+
+    /// Adds the operator spelled `text`, replacing any previous entry for it.
+    pub fn infix(mut self, text: &'static str, op: BinaryOp, power: u8, assoc: Assoc) -> Self {
+        let binding = Binding { op, power, assoc };
+        match self.infix.iter_mut().find(|(known, _)| *known == text) {
+            Some(entry) => entry.1 = binding,
+            None => self.infix.push((text, binding)),
+        }
+        self
+    }
+
+    pub fn binding(&self, text: &str) -> Option<Binding> {
+        self.infix.iter().find(|(known, _)| *known == text).map(|&(_, binding)| binding)
+    }
+}
+
+impl Default for OperatorTable {
+    fn default() -> Self {
+        OperatorTable::standard()
+    }
+}
+
+/// Parses `tokens` into statements up to EOF, with the operators of
+/// [`OperatorTable::standard`].
+///
+/// A statement is `let pattern = value`, `const pattern = value`,
+/// `defer expression`, `defer { ... }`, a `{ ... }` block of statements or a
+/// bare expression, nothing separates two statements.
+/// A declaration always has a value, `let x` and `const x` are errors
+/// expecting the `=`. The pattern is a name or destructures the value, like
+/// `[a, b]` or `{ x, y: { z } }`.
+pub fn parse_program(tokens: &[Token<'_>]) -> Result<Program, ParseError> {
+    let mut parser = Parser { tokens: TokenStream::new(tokens), operators: &OperatorTable::standard(), depth: 0, nesting: 0 };
+    let mut statements = Vec::new();
+    while !parser.tokens.at_eof() {
+        statements.push(parser.statement()?);
+    }
+    Ok(parser.program(statements))
+}
+
+/// Like [`parse_program`], but keeps going after an error: the tokens up to the
+/// next `let`, `const` or `defer` are skipped and stand in the program as a
+/// single [`Stmt::Error`]. An error inside a block skips through the block's
+/// `}`, whatever statements come before it. Every error is returned, in source
+/// order.
+pub fn parse_program_recovering(tokens: &[Token<'_>]) -> (Program, Vec<ParseError>) {
+    let mut parser = Parser { tokens: TokenStream::new(tokens), operators: &OperatorTable::standard(), depth: 0, nesting: 0 };
+    let (mut statements, mut errors) = (Vec::new(), Vec::new());
+    while !parser.tokens.at_eof() {
+        let start = parser.tokens.position();
+        let first = parser.tokens.peek().span;
+        match parser.statement() {
+            Ok(statement) => statements.push(statement),
+            Err(err) => {
+                errors.push(err);
+                parser.synchronize(start);
+                // `synchronize` skips at least the first token of the statement
+                let last = parser.tokens.previous().map_or(first, |token| token.span);
+                statements.push(Stmt::Error(Span::merge(first, last)));
+            }
+        }
+    }
+    (parser.program(statements), errors)
+}
+
+/// Parses `tokens`, which must hold a single expression and nothing after it
+/// but EOF, with the operators of [`OperatorTable::standard`].
+pub fn parse_expression(tokens: &[Token<'_>]) -> Result<Expr, ParseError> {
+    parse_expression_with(tokens, &OperatorTable::standard())
+}
+
+/// Like [`parse_expression`], knowing only the infix operators of `operators`.
+pub fn parse_expression_with(tokens: &[Token<'_>], operators: &OperatorTable) -> Result<Expr, ParseError> {
+    let mut parser = Parser { tokens: TokenStream::new(tokens), operators, depth: 0, nesting: 0 };
+    let expr = parser.expression(0)?;
+    parser.expect(TokenType::Eof, "after expression")?;
+    Ok(expr)
+}
+
+type PrefixRule<'a, 'src> = fn(&mut Parser<'a, 'src>) -> Result<Expr, ParseError>;
+
+// Pratt parser, an operand from the prefix rule of its first token, then
+// infix operators, casts, assignments and `?.` as long as they bind at least as tight as
+// `min_power`
+struct Parser<'a, 'src> {
+    tokens: TokenStream<'a, 'src>,
+    operators: &'a OperatorTable,
+    // blocks opened and not closed yet, an error inside a block leaves them open
+    // for `synchronize` to skip
+    depth: usize,
+    // levels of `nested` calls the parser is in, see `MAX_NESTING`
+    nesting: usize,
+}
+
+impl Parser<'_, '_> {
+    fn program(&self, statements: Vec<Stmt>) -> Program {
+        let span = match (statements.first(), statements.last()) {
+            (Some(first), Some(last)) => Span::merge(first.span(), last.span()),
+            _ => self.tokens.peek().span,
+        };
+        Program { statements, span }
+    }
+
+    // error for finding the current token where `expected` should be
+    fn error(&self, expected: Expected) -> ParseError {
+        self.error_at(self.tokens.position(), expected)
+    }
+
+    // error for finding the token at `position`, from `TokenStream::position`,
+    // where `expected` should be
+    fn error_at(&self, position: usize, expected: Expected) -> ParseError {
+        let found = self.tokens.token_at(position);
+        ParseError { span: found.span, found: found.kind, expected, context: None, unclosed: None }
+    }
+
+    // consumes a token of the given kind, `context` tells where the parser was
+    // when it is missing
+    fn expect(&mut self, kind: TokenType, context: &'static str) -> Result<&Token<'_>, ParseError> {
+        self.tokens.expect(kind).map_err(|err| ParseError::from(err).with_context(context))
+    }
+
+    // skips to the start of the next statement after an error in the one that
+    // started at `start`, always moving past at least one token so a statement
+    // failing on its first token can't fail there again. Blocks the error left
+    // open are skipped through their `}`, a `}` outside them is not skipped since
+    // it doesn't belong to the failed statement.
+    fn synchronize(&mut self, start: usize) {
+        if self.tokens.position() == start {
+            self.tokens.next();
+        }
+        loop {
+            match self.tokens.peek().kind {
+                TokenType::Eof => return,
+                TokenType::Let | TokenType::Const | TokenType::Defer if self.depth == 0 => return,
+                TokenType::CloseBrace if self.depth == 0 => return,
+                TokenType::OpenBrace => self.depth += 1,
+                TokenType::CloseBrace => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        self.tokens.next();
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.tokens.next();
+        }
+    }
+
+    fn statement(&mut self) -> Result<Stmt, ParseError> {
+        match self.tokens.peek().kind {
+            TokenType::Let | TokenType::Const => self.let_statement(),
+            TokenType::Defer => self.defer_statement(),
+            TokenType::OpenBrace => Ok(Stmt::Block(self.block()?)),
+            _ => Ok(Stmt::Expr(self.expression(0)?)),
+        }
+    }
+
+    fn defer_statement(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.tokens.next().span;
+        let body = if self.tokens.check(TokenType::OpenBrace) {
+            Stmt::Block(self.block()?)
+        } else {
+            Stmt::Expr(self.expression(0).map_err(|err| err.with_context("after 'defer'"))?)
+        };
+        let span = Span::merge(start, body.span());
+        Ok(Stmt::Defer { body: Box::new(body), span })
+    }
+
+    // reaching EOF before the `}` is reported along with the `{` of the
+    // innermost block still open, not as a missing statement at the end of the
+    // file
+    fn block(&mut self) -> Result<Block, ParseError> {
+        let open = self.tokens.peek().span;
+        self.nested(Expected::Token(TokenType::CloseBrace), |parser| {
+            parser.tokens.next();
+            parser.depth += 1;
+            let mut statements = Vec::new();
+            while !parser.tokens.check(TokenType::CloseBrace) {
+                if parser.tokens.at_eof() {
+                    let mut err = parser.error(Expected::Token(TokenType::CloseBrace));
+                    err.unclosed = Some(open);
+                    return Err(err);
+                }
+                statements.push(parser.statement()?);
+            }
+            let close = parser.tokens.next().span;
+            parser.depth -= 1;
+            Ok(Block { statements, span: Span::merge(open, close) })
+        })
+    }
+
+    fn let_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.tokens.next();
+        let (constant, start) = (keyword.kind == TokenType::Const, keyword.span);
+        let pattern = self.pattern()?;
+        if constant && !self.tokens.check(TokenType::Equals) {
+            return Err(self.error(Expected::ConstantValue).with_context("since constants need a value"));
+        }
+        self.expect(TokenType::Equals, "after let pattern")?;
+        let value = self.expression(0)?;
+        let span = Span::merge(start, value.span());
+        Ok(Stmt::Let { constant, pattern, value, span })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, ParseError> {
+        self.nested(Expected::Pattern, |parser| match parser.tokens.peek().kind {
+            TokenType::Identifier => {
+                let token = parser.tokens.next();
+                Ok(Pattern::Name { name: token.value().to_string(), span: token.span })
+            }
+            TokenType::OpenBracket => {
+                let (elements, span) = parser.comma_separated(TokenType::CloseBracket, "in array pattern", Parser::pattern)?;
+                Ok(Pattern::Array { elements, span })
+            }
+            TokenType::OpenBrace => {
+                let (fields, span) = parser.comma_separated(TokenType::CloseBrace, "in object pattern", Parser::field_pattern)?;
+                Ok(Pattern::Object { fields, span })
+            }
+            _ => Err(parser.error(Expected::Pattern)),
+        })
+    }
+
+    fn field_pattern(&mut self) -> Result<FieldPattern, ParseError> {
+        let token = self.expect(TokenType::Identifier, "in object pattern")?;
+        let (key, key_span) = (token.value().to_string(), token.span);
+        if self.tokens.check(TokenType::Colon) {
+            self.tokens.next();
+            let value = self.pattern()?;
+            let span = Span::merge(key_span, value.span());
+            return Ok(FieldPattern { key, value, span });
+        }
+        let value = Pattern::Name { name: key.clone(), span: key_span };
+        Ok(FieldPattern { key, value, span: key_span })
+    }
+
+    // the opening delimiter, then `item`s separated by commas up to `close`,
+    // with the span from one delimiter to the other; a missing `close` is
+    // reported with `context`
+    fn comma_separated<T>(
+        &mut self,
+        close: TokenType,
+        context: &'static str,
+        item: fn(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<(Vec<T>, Span), ParseError> {
+        let open = self.tokens.next().span;
+        let mut items = Vec::new();
+        if !self.tokens.check(close) {
+            items.push(item(self)?);
+            while self.tokens.check(TokenType::Comma) {
+                self.tokens.next();
+                items.push(item(self)?);
+            }
+        }
+        let end = self.expect(close, context)?.span;
+        Ok((items, Span::merge(open, end)))
+    }
+
+    // `parse` one level deeper, or an error expecting `expected` at the current
+    // token when that is past `MAX_NESTING`
+    fn nested<T>(&mut self, expected: Expected, parse: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Result<T, ParseError> {
+        if self.nesting == MAX_NESTING {
+            return Err(self.error(expected).with_context("(nested too deeply)"));
+        }
+        self.nesting += 1;
+        let result = parse(self);
+        self.nesting -= 1;
+        result
+    }
+
+    fn expression(&mut self, min_power: u8) -> Result<Expr, ParseError> {
+        self.nested(Expected::Expression, |parser| parser.operation(min_power))
+    }
+
+    // an operand, then the operators binding at least as tight as `min_power`
+    fn operation(&mut self, min_power: u8) -> Result<Expr, ParseError> {
+        let start = self.tokens.position();
+        let rule = prefix_rule(self.tokens.peek()).ok_or_else(|| self.error(Expected::Expression))?;
+        let mut lhs = rule(self)?;
+        loop {
+            if self.tokens.check(TokenType::Equals) && ASSIGN_POWER >= min_power {
+                if !is_assignable(&lhs) {
+                    return Err(self.error_at(start, Expected::AssignmentTarget));
+                }
+                self.tokens.next();
+                let value = self.expression(ASSIGN_POWER)?;
+                let span = Span::merge(lhs.span(), value.span());
+                lhs = Expr::Assign { target: Box::new(lhs), value: Box::new(value), span };
+                continue;
+            }
+            if self.tokens.check(TokenType::As) && CAST_POWER >= min_power {
+                lhs = self.cast(lhs)?;
+                continue;
+            }
+            if self.tokens.check(TokenType::SafeNavigation) && POSTFIX_POWER >= min_power {
+                lhs = self.optional_member(lhs)?;
+                continue;
+            }
+            let Some(binding) = self.infix_binding().filter(|binding| binding.power >= min_power) else {
+                break;
+            };
+            self.tokens.next();
+            // the right operand of a left-associative operator stops at the
+            // same power, so the next one of it groups with the whole `lhs`
+            let rhs = match binding.assoc {
+                Assoc::Left => self.expression(binding.power + 1)?,
+                Assoc::Right => self.expression(binding.power)?,
+            };
+            let span = Span::merge(lhs.span(), rhs.span());
+            lhs = Expr::Binary { op: binding.op, lhs: Box::new(lhs), rhs: Box::new(rhs), span };
+        }
+        Ok(lhs)
+    }
+
+    fn cast(&mut self, value: Expr) -> Result<Expr, ParseError> {
+        self.tokens.next();
+        let target = self.expect(TokenType::Identifier, "after 'as'")?;
+        let (target_type, end) = (target.value().to_string(), target.span);
+        let span = Span::merge(value.span(), end);
+        Ok(Expr::Cast { value: Box::new(value), target_type, span })
+    }
+
+    fn optional_member(&mut self, object: Expr) -> Result<Expr, ParseError> {
+        self.tokens.next();
+        let field = self.expect(TokenType::Identifier, "after '?.'")?;
+        let (name, end) = (field.value().to_string(), field.span);
+        let span = Span::merge(object.span(), end);
+        Ok(Expr::OptionalMember { object: Box::new(object), field: name, span })
+    }
+
+    // binding of the current token, if it is a known infix operator
+    fn infix_binding(&self) -> Option<Binding> {
+        let token = self.tokens.peek();
+        match token.kind {
+            TokenType::BinaryOperator => self.operators.binding(token.value()),
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> Result<Expr, ParseError> {
+        // `is_numeric` lets in digits `f64` doesn't parse, like `٣`
+        let value = self.tokens.peek().value().parse().map_err(|_| self.error(Expected::Expression))?;
+        let span = self.tokens.next().span;
+        Ok(Expr::NumberLiteral { value, span })
+    }
+
+    fn identifier(&mut self) -> Result<Expr, ParseError> {
+        let token = self.tokens.next();
+        Ok(Expr::Identifier { name: token.value().to_string(), span: token.span })
+    }
+
+    // the parentheses belong to the span of the expression they wrap
+    fn group(&mut self) -> Result<Expr, ParseError> {
+        let open = self.tokens.next().span;
+        let mut expr = self.expression(0)?;
+        let close = self.expect(TokenType::CloseParen, "after expression")?.span;
+        *span_mut(&mut expr) = Span::merge(open, close);
+        Ok(expr)
+    }
+
+    // a literal keeps its sign as a `Neg` node, `-5` is not folded into a number
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        let token = self.tokens.next();
+        let op = if token.kind == TokenType::Bang { UnaryOp::Not } else { UnaryOp::Neg };
+        let start = token.span;
+        let operand = self.expression(PREFIX_POWER)?;
+        let span = Span::merge(start, operand.span());
+        Ok(Expr::Unary { op, operand: Box::new(operand), span })
+    }
+}
+
+fn span_mut(expr: &mut Expr) -> &mut Span {
+    match expr {
+        Expr::NumberLiteral { span, .. }
+        | Expr::Identifier { span, .. }
+        | Expr::Unary { span, .. }
+        | Expr::Binary { span, .. }
+        | Expr::OptionalMember { span, .. }
+        | Expr::Assign { span, .. }
+        | Expr::Cast { span, .. } => span,
+    }
+}
+
+// whether `expr` can be on the left of `=`, a parenthesized name can
+fn is_assignable(expr: &Expr) -> bool {
+    matches!(expr, Expr::Identifier { .. })
+}
+
+// how an expression starting with `token` is parsed, `None` if none can
+fn prefix_rule<'a, 'src>(token: &Token<'_>) -> Option<PrefixRule<'a, 'src>> {
+    match token.kind {
+        TokenType::Number => Some(Parser::number),
+        TokenType::Identifier => Some(Parser::identifier),
+        TokenType::OpenParen => Some(Parser::group),
+        TokenType::BinaryOperator if token.value() == "-" => Some(Parser::unary),
+        TokenType::Bang => Some(Parser::unary),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use crate::ast::{BinaryOp, Block, Expr, FieldPattern, Pattern, Spanned, Stmt, UnaryOp};
+    use crate::lexer::{tokenize, Lexer, LexerConfig, Span, Token, TokenType};
+    use crate::parser::{
+        parse_expression, parse_expression_with, parse_program, parse_program_recovering, Assoc, Expected,
+        OperatorTable, ParseError,
+    };
+
+    // hand-built trees have no source, their spans are all this one
+    const NO_SPAN: Span = Span { start: 0, end: 0, line: 0, column: 0 };
+
+    fn parse(source: &str) -> Result<Expr, ParseError> {
+        parse_expression(&tokenize(source).unwrap()).map(unspanned)
+    }
+
+    fn unspanned(expr: Expr) -> Expr {
+        match expr {
+            Expr::NumberLiteral { value, .. } => num(value),
+            Expr::Identifier { name, .. } => Expr::Identifier { name, span: NO_SPAN },
+            Expr::Unary { op, operand, .. } => Expr::Unary { op, operand: Box::new(unspanned(*operand)), span: NO_SPAN },
+            Expr::Binary { op, lhs, rhs, .. } => binary(unspanned(*lhs), op, unspanned(*rhs)),
+            Expr::OptionalMember { object, field, .. } => member(unspanned(*object), &field),
+            Expr::Assign { target, value, .. } => assign(unspanned(*target), unspanned(*value)),
+            Expr::Cast { value, target_type, .. } => cast(unspanned(*value), &target_type),
+        }
+    }
+
+    fn unspanned_stmt(stmt: Stmt) -> Stmt {
+        match stmt {
+            Stmt::Let { constant, pattern, value, .. } => {
+                Stmt::Let { constant, pattern: unspanned_pattern(pattern), value: unspanned(value), span: NO_SPAN }
+            }
+            Stmt::Defer { body, .. } => Stmt::Defer { body: Box::new(unspanned_stmt(*body)), span: NO_SPAN },
+            Stmt::Block(block) => block_of(block.statements.into_iter().map(unspanned_stmt).collect()),
+            Stmt::Expr(expr) => Stmt::Expr(unspanned(expr)),
+            Stmt::Error(_) => Stmt::Error(NO_SPAN),
+        }
+    }
+
+    fn unspanned_pattern(pattern: Pattern) -> Pattern {
+        match pattern {
+            Pattern::Name { name, .. } => Pattern::Name { name, span: NO_SPAN },
+            Pattern::Array { elements, .. } => {
+                Pattern::Array { elements: elements.into_iter().map(unspanned_pattern).collect(), span: NO_SPAN }
+            }
+            Pattern::Object { fields, .. } => {
+                let fields = fields
+                    .into_iter()
+                    .map(|field| FieldPattern { key: field.key, value: unspanned_pattern(field.value), span: NO_SPAN })
+                    .collect();
+                Pattern::Object { fields, span: NO_SPAN }
+            }
+        }
+    }
+
+    fn parse_pattern(source: &str) -> Result<Pattern, ParseError> {
+        let program = parse_program(&tokenize(source).unwrap())?;
+        match program.statements.into_iter().next() {
+            Some(Stmt::Let { pattern, .. }) => Ok(unspanned_pattern(pattern)),
+            other => panic!("expected a let, got {:?}", other),
+        }
+    }
+
+    fn block_of(statements: Vec<Stmt>) -> Stmt {
+        Stmt::Block(Block { statements, span: NO_SPAN })
+    }
+
+    fn parse_statements(source: &str) -> Result<Vec<Stmt>, ParseError> {
+        let program = parse_program(&tokenize(source).unwrap())?;
+        Ok(program.statements.into_iter().map(unspanned_stmt).collect())
+    }
+
+    fn name(name: &str) -> Pattern {
+        Pattern::Name { name: name.to_string(), span: NO_SPAN }
+    }
+
+    fn field(key: &str, value: Pattern) -> FieldPattern {
+        FieldPattern { key: key.to_string(), value, span: NO_SPAN }
+    }
+
+    fn num(value: f64) -> Expr {
+        Expr::NumberLiteral { value, span: NO_SPAN }
+    }
+
+    fn ident(name: &str) -> Expr {
+        Expr::Identifier { name: name.to_string(), span: NO_SPAN }
+    }
+
+    fn binary(lhs: Expr, op: BinaryOp, rhs: Expr) -> Expr {
+        Expr::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs), span: NO_SPAN }
+    }
+
+    fn neg(operand: Expr) -> Expr {
+        Expr::Unary { op: UnaryOp::Neg, operand: Box::new(operand), span: NO_SPAN }
+    }
+
+    fn not(operand: Expr) -> Expr {
+        Expr::Unary { op: UnaryOp::Not, operand: Box::new(operand), span: NO_SPAN }
+    }
+
+    fn member(object: Expr, field: &str) -> Expr {
+        Expr::OptionalMember { object: Box::new(object), field: field.to_string(), span: NO_SPAN }
+    }
+
+    fn assign(target: Expr, value: Expr) -> Expr {
+        Expr::Assign { target: Box::new(target), value: Box::new(value), span: NO_SPAN }
+    }
+
+    fn cast(value: Expr, target_type: &str) -> Expr {
+        Expr::Cast { value: Box::new(value), target_type: target_type.to_string(), span: NO_SPAN }
+    }
+
+    #[test]
+    fn should_parse_operands(){
+        assert_eq!(num(45.0), parse("45").unwrap());
+        assert_eq!(ident("total"), parse("total").unwrap());
+        assert_eq!(ident("x"), parse("((x))").unwrap());
+    }
+
+    #[test]
+    fn should_bind_multiplication_tighter_than_addition(){
+        let expected = binary(num(1.0), BinaryOp::Add, binary(num(2.0), BinaryOp::Mul, num(3.0)));
+        assert_eq!(expected, parse("1 + 2 * 3").unwrap());
+        let expected = binary(binary(num(1.0), BinaryOp::Mod, num(2.0)), BinaryOp::Sub, num(3.0));
+        assert_eq!(expected, parse("1 % 2 - 3").unwrap());
+    }
+
+    #[test]
+    fn should_respect_parentheses(){
+        let expected = binary(binary(num(1.0), BinaryOp::Add, num(2.0)), BinaryOp::Mul, num(3.0));
+        assert_eq!(expected, parse("(1 + 2) * 3").unwrap());
+        let expected = binary(ident("a"), BinaryOp::Div, binary(ident("b"), BinaryOp::Sub, ident("c")));
+        assert_eq!(expected, parse("a / (b - c)").unwrap());
+    }
+
+    #[test]
+    fn should_group_same_precedence_to_the_left(){
+        let expected = binary(binary(num(8.0), BinaryOp::Sub, num(4.0)), BinaryOp::Sub, num(2.0));
+        assert_eq!(expected, parse("8 - 4 - 2").unwrap());
+        let expected = binary(binary(ident("a"), BinaryOp::Div, ident("b")), BinaryOp::Mul, ident("c"));
+        assert_eq!(expected, parse("a / b * c").unwrap());
+    }
+
+    #[test]
+    fn should_climb_every_precedence_level(){
+        let power = binary(num(3.0), BinaryOp::Pow, num(2.0));
+        let sum = binary(num(1.0), BinaryOp::Add, binary(num(2.0), BinaryOp::Mul, power));
+        assert_eq!(binary(sum, BinaryOp::Eq, num(19.0)), parse("1 + 2 * 3 ** 2 == 19").unwrap());
+        let power = binary(num(2.0), BinaryOp::Pow, num(3.0));
+        let product = binary(binary(power, BinaryOp::Mul, num(4.0)), BinaryOp::Mod, num(5.0));
+        assert_eq!(binary(num(1.0), BinaryOp::Eq, product), parse("1 == 2 ** 3 * 4 % 5").unwrap());
+    }
+
+    #[test]
+    fn should_group_exponents_to_the_right(){
+        let expected = binary(num(2.0), BinaryOp::Pow, binary(num(3.0), BinaryOp::Pow, num(2.0)));
+        assert_eq!(expected, parse("2 ** 3 ** 2").unwrap());
+        let expected = binary(binary(num(2.0), BinaryOp::Pow, num(3.0)), BinaryOp::Pow, num(2.0));
+        assert_eq!(expected, parse("(2 ** 3) ** 2").unwrap());
+        let expected = binary(binary(ident("a"), BinaryOp::Eq, ident("b")), BinaryOp::Eq, ident("c"));
+        assert_eq!(expected, parse("a == b == c").unwrap());
+    }
+
+    #[test]
+    fn should_parse_unary_minus_as_a_prefix(){
+        // a negative literal stays a negation of the literal
+        assert_eq!(neg(num(1.0)), parse("-1").unwrap());
+        assert_eq!(neg(neg(ident("x"))), parse("- -x").unwrap());
+        assert_eq!(neg(binary(num(1.0), BinaryOp::Add, num(2.0))), parse("-(1 + 2)").unwrap());
+        // tighter than every binary operator
+        assert_eq!(binary(neg(num(5.0)), BinaryOp::Mul, num(3.0)), parse("-5 * 3").unwrap());
+        assert_eq!(binary(neg(num(2.0)), BinaryOp::Pow, num(2.0)), parse("-2 ** 2").unwrap());
+        assert_eq!(binary(num(2.0), BinaryOp::Pow, neg(num(1.0))), parse("2 ** -1").unwrap());
+        assert_eq!(binary(num(1.0), BinaryOp::Sub, neg(num(1.0))), parse("1 - -1").unwrap());
+        // `--` is a decrement, not two minuses, and there are no decrements
+        // in expressions yet
+        let error = parse("--x").unwrap_err();
+        assert_eq!((Expected::Expression, TokenType::MinusMinus), (error.expected, error.found));
+    }
+
+    #[test]
+    fn should_parse_logical_not_as_a_prefix(){
+        assert_eq!(not(ident("flag")), parse("!flag").unwrap());
+        assert_eq!(not(not(ident("flag"))), parse("!!flag").unwrap());
+        assert_eq!(binary(not(ident("x")), BinaryOp::Eq, ident("y")), parse("!x == y").unwrap());
+        assert_eq!(not(neg(ident("x"))), parse("!-x").unwrap());
+        // looser than `?.`
+        assert_eq!(not(member(ident("user"), "admin")), parse("!user?.admin").unwrap());
+
+        let expr = parse_expression(&tokenize("!(a == b)").unwrap()).unwrap();
+        assert_eq!(Span::new(0, 9, 1, 1), expr.span());
+        let error = parse("x !").unwrap_err();
+        assert_eq!((Expected::Token(TokenType::Eof), TokenType::Bang), (error.expected, error.found));
+        assert_eq!(Expected::Expression, parse("!").unwrap_err().expected);
+    }
+
+    #[test]
+    fn should_parse_casts_between_unary_minus_and_multiplication(){
+        assert_eq!(cast(ident("count"), "String"), parse("count as String").unwrap());
+        assert_eq!(cast(neg(ident("x")), "Number"), parse("-x as Number").unwrap());
+        let expected = binary(ident("a"), BinaryOp::Mul, cast(ident("b"), "Number"));
+        assert_eq!(expected, parse("a * b as Number").unwrap());
+        let expected = binary(cast(ident("a"), "Number"), BinaryOp::Add, num(1.0));
+        assert_eq!(expected, parse("a as Number + 1").unwrap());
+        let power = binary(num(2.0), BinaryOp::Pow, num(3.0));
+        assert_eq!(cast(power, "String"), parse("2 ** 3 as String").unwrap());
+        assert_eq!(cast(cast(ident("flag"), "Number"), "String"), parse("flag as Number as String").unwrap());
+    }
+
+    #[test]
+    fn should_require_a_type_name_after_as(){
+        let error = parse("x as 1").unwrap_err();
+        assert_eq!(Expected::Token(TokenType::Identifier), error.expected);
+        assert_eq!("expected identifier after 'as', found number at 1:6", error.to_string());
+        assert_eq!(Expected::Token(TokenType::Identifier), parse("x as").unwrap_err().expected);
+        assert_eq!(Expected::Expression, parse("as Number").unwrap_err().expected);
+
+        let expr = parse_expression(&tokenize("(a + 1) as String").unwrap()).unwrap();
+        assert_eq!(Span::new(0, 17, 1, 1), expr.span());
+    }
+
+    #[test]
+    fn should_chain_optional_members_to_the_left(){
+        let city = member(member(member(ident("user"), "address"), "city"), "name");
+        assert_eq!(city, parse("user?.address?.city?.name").unwrap());
+        assert_eq!(neg(member(ident("a"), "b")), parse("-a?.b").unwrap());
+        assert_eq!(binary(num(2.0), BinaryOp::Pow, member(ident("a"), "b")), parse("2 ** a?.b").unwrap());
+        assert_eq!(member(binary(ident("a"), BinaryOp::Add, ident("b")), "c"), parse("(a + b)?.c").unwrap());
+
+        let expr = parse_expression(&tokenize("user?.address?.city").unwrap()).unwrap();
+        assert_eq!(Span::new(0, 19, 1, 1), expr.span());
+        let error = parse("user?.1").unwrap_err();
+        assert_eq!(Expected::Token(TokenType::Identifier), error.expected);
+        assert_eq!("expected identifier after '?.', found number at 1:7", error.to_string());
+    }
+
+    #[test]
+    fn should_coalesce_below_every_other_operator(){
+        let expected = binary(binary(ident("a"), BinaryOp::Coalesce, ident("b")), BinaryOp::Coalesce, ident("c"));
+        assert_eq!(expected, parse("a ?? b ?? c").unwrap());
+        let expected = binary(ident("a"), BinaryOp::Coalesce, binary(ident("b"), BinaryOp::Eq, num(1.0)));
+        assert_eq!(expected, parse("a ?? b == 1").unwrap());
+        let expected = binary(member(member(ident("config"), "retry"), "limit"), BinaryOp::Coalesce, num(3.0));
+        assert_eq!(expected, parse("config?.retry?.limit ?? 3").unwrap());
+    }
+
+    #[test]
+    fn should_assign_to_the_right(){
+        assert_eq!(assign(ident("a"), assign(ident("b"), num(3.0))), parse("a = b = 3").unwrap());
+        let sum = binary(num(1.0), BinaryOp::Add, num(2.0));
+        assert_eq!(assign(ident("x"), sum), parse("x = 1 + 2").unwrap());
+        let coalesced = binary(ident("a"), BinaryOp::Coalesce, num(0.0));
+        assert_eq!(assign(ident("x"), coalesced), parse("x = a ?? 0").unwrap());
+        assert_eq!(binary(num(1.0), BinaryOp::Add, assign(ident("x"), num(2.0))), parse("1 + (x = 2)").unwrap());
+        assert_eq!(assign(ident("x"), num(1.0)), parse("(x) = 1").unwrap());
+
+        let program = parse_program(&tokenize("let a = b = 1").unwrap()).unwrap();
+        let Stmt::Let { value, .. } = &program.statements[0] else { panic!("expected a let, got {:?}", program.statements[0]) };
+        assert_eq!(assign(ident("b"), num(1.0)), unspanned(value.clone()));
+        assert_eq!(Span::new(8, 13, 1, 9), value.span());
+    }
+
+    #[test]
+    fn should_reject_invalid_assignment_targets(){
+        let error = parse("1 + 2 = 3").unwrap_err();
+        assert_eq!(Expected::AssignmentTarget, error.expected);
+        assert_eq!("expected assignment target, found number at 1:1", error.to_string());
+        let error = parse("x + (a + b) = 1").unwrap_err();
+        assert_eq!((Expected::AssignmentTarget, 1), (error.expected, error.span.column));
+        let error = parse("(a + b) = 1").unwrap_err();
+        assert_eq!((Expected::AssignmentTarget, TokenType::OpenParen), (error.expected, error.found));
+        let error = parse("a?.b = 1").unwrap_err();
+        assert_eq!(Expected::AssignmentTarget, error.expected);
+        // groups to the right, so `1` is the target of the second `=`
+        let error = parse("a = 1 = 2").unwrap_err();
+        assert_eq!((Expected::AssignmentTarget, 5), (error.expected, error.span.column));
+        let error = parse("-x = 1").unwrap_err();
+        assert_eq!((Expected::AssignmentTarget, 1), (error.expected, error.span.column));
+    }
+
+    #[test]
+    fn should_parse_only_the_operators_of_the_table(){
+        let tokens = tokenize("a + b * c").unwrap();
+        let error = parse_expression_with(&tokens, &OperatorTable::empty()).unwrap_err();
+        assert_eq!(Expected::Token(TokenType::Eof), error.expected);
+        assert_eq!((TokenType::BinaryOperator, 3), (error.found, error.span.column));
+
+        // registering an operator is enough to parse it
+        let table = OperatorTable::empty()
+            .infix("+", BinaryOp::Add, 1, Assoc::Left)
+            .infix("*", BinaryOp::Mul, 2, Assoc::Left);
+        let expected = binary(ident("a"), BinaryOp::Add, binary(ident("b"), BinaryOp::Mul, ident("c")));
+        assert_eq!(expected, parse_expression_with(&tokens, &table).map(unspanned).unwrap());
+
+        // and re-registering it changes how it binds
+        let table = table.infix("*", BinaryOp::Mul, 0, Assoc::Left);
+        let expected = binary(binary(ident("a"), BinaryOp::Add, ident("b")), BinaryOp::Mul, ident("c"));
+        assert_eq!(expected, parse_expression_with(&tokens, &table).map(unspanned).unwrap());
+        let table = OperatorTable::standard().infix("-", BinaryOp::Sub, 20, Assoc::Right);
+        let tokens = tokenize("8 - 4 - 2").unwrap();
+        let expected = binary(num(8.0), BinaryOp::Sub, binary(num(4.0), BinaryOp::Sub, num(2.0)));
+        assert_eq!(expected, parse_expression_with(&tokens, &table).map(unspanned).unwrap());
+    }
+
+    #[test]
+    fn should_span_a_let_from_the_keyword_through_its_value(){
+        let program = parse_program(&tokenize("let x = (1 + 2)").unwrap()).unwrap();
+        let [Stmt::Let { pattern, value, span, .. }] = program.statements.as_slice() else {
+            panic!("expected a single let, got {:?}", program.statements);
+        };
+        assert_eq!(Span::new(4, 5, 1, 5), pattern.span());
+        assert_eq!(Span::new(0, 15, 1, 1), *span);
+        assert_eq!(*span, program.span());
+        // the parentheses are part of the sum they wrap
+        assert_eq!(Span::new(8, 15, 1, 9), value.span());
+        let Expr::Binary { lhs, rhs, .. } = value else { panic!("expected a sum, got {:?}", value) };
+        assert_eq!(Span::new(9, 10, 1, 10), lhs.span());
+        assert_eq!(Span::new(13, 14, 1, 14), rhs.span());
+    }
+
+    #[test]
+    fn should_merge_the_spans_of_children(){
+        let expr = parse_expression(&tokenize("(a) * -(b + 1)").unwrap()).unwrap();
+        assert_eq!(Span::new(0, 14, 1, 1), expr.span());
+        let Expr::Binary { lhs, rhs, .. } = &expr else { panic!("expected a product, got {:?}", expr) };
+        assert_eq!(Span::new(0, 3, 1, 1), lhs.span());
+        assert_eq!(Span::new(6, 14, 1, 7), rhs.span());
+        let Expr::Unary { operand, .. } = rhs.as_ref() else { panic!("expected a negation, got {:?}", rhs) };
+        assert_eq!(Span::new(7, 14, 1, 8), operand.span());
+    }
+
+    #[test]
+    fn should_parse_statements_up_to_eof(){
+        let program = parse_program(&tokenize("let a = 1\n  a * 2\n").unwrap()).unwrap();
+        assert_eq!(2, program.statements.len());
+        assert_eq!(Span::new(12, 17, 2, 3), program.statements[1].span());
+        let Stmt::Expr(expr) = &program.statements[1] else { panic!("expected an expression, got {:?}", program.statements[1]) };
+        assert_eq!(binary(ident("a"), BinaryOp::Mul, num(2.0)), unspanned(expr.clone()));
+        assert_eq!(Span::new(0, 17, 1, 1), program.span());
+
+        let empty = parse_program(&tokenize("  // nothing\n").unwrap()).unwrap();
+        assert!(empty.statements.is_empty());
+        assert_eq!(Span::new(13, 13, 2, 1), empty.span());
+    }
+
+    #[test]
+    fn should_parse_let_declarations(){
+        let program = parse_program(&tokenize("let a = 1 let b = a * 2\nb").unwrap()).unwrap();
+        let statements: Vec<Stmt> = program.statements.into_iter().map(unspanned_stmt).collect();
+        let expected = vec![
+            Stmt::Let { constant: false, pattern: name("a"), value: num(1.0), span: NO_SPAN },
+            Stmt::Let { constant: false, pattern: name("b"), value: binary(ident("a"), BinaryOp::Mul, num(2.0)), span: NO_SPAN },
+            Stmt::Expr(ident("b")),
+        ];
+        assert_eq!(expected, statements);
+    }
+
+    #[test]
+    fn should_require_a_name_and_a_value_in_let(){
+        let error = parse_program(&tokenize("let = 1").unwrap()).unwrap_err();
+        assert_eq!(Expected::Pattern, error.expected);
+        assert_eq!("expected pattern, found '=' at 1:5", error.to_string());
+
+        let error = parse_program(&tokenize("let x 1").unwrap()).unwrap_err();
+        assert_eq!(Expected::Token(TokenType::Equals), error.expected);
+        assert_eq!("expected '=' after let pattern, found number at 1:7", error.to_string());
+        // no value at all is the same missing `=`
+        let error = parse_program(&tokenize("let x\nx").unwrap()).unwrap_err();
+        assert_eq!(Expected::Token(TokenType::Equals), error.expected);
+        assert_eq!((2, 1), (error.span.line, error.span.column));
+        let error = parse_program(&tokenize("let x").unwrap()).unwrap_err();
+        assert_eq!(TokenType::Eof, error.found);
+    }
+
+    #[test]
+    fn should_parse_const_declarations(){
+        let program = parse_program(&tokenize("const LIMIT = 10 * 2\nlet count = LIMIT").unwrap()).unwrap();
+        let statements: Vec<Stmt> = program.statements.into_iter().map(unspanned_stmt).collect();
+        let expected = vec![
+            Stmt::Let { constant: true, pattern: name("LIMIT"), value: binary(num(10.0), BinaryOp::Mul, num(2.0)), span: NO_SPAN },
+            Stmt::Let { constant: false, pattern: name("count"), value: ident("LIMIT"), span: NO_SPAN },
+        ];
+        assert_eq!(expected, statements);
+        let program = parse_program(&tokenize("const [a, b] = pair").unwrap()).unwrap();
+        assert_eq!(Span::new(0, 19, 1, 1), program.statements[0].span());
+    }
+
+    #[test]
+    fn should_require_a_value_for_a_constant(){
+        let error = parse_program(&tokenize("const x").unwrap()).unwrap_err();
+        assert_eq!(Expected::ConstantValue, error.expected);
+        assert_eq!(
+            "expected '=' and a value since constants need a value, found end of file at 1:8",
+            error.to_string()
+        );
+        let error = parse_program(&tokenize("const x\nx = 1").unwrap()).unwrap_err();
+        assert_eq!((Expected::ConstantValue, 2), (error.expected, error.span.line));
+        // the value itself is an ordinary expression
+        let error = parse_program(&tokenize("const x =").unwrap()).unwrap_err();
+        assert_eq!(Expected::Expression, error.expected);
+    }
+
+    #[test]
+    fn should_reject_const_where_an_expression_is_expected(){
+        let error = parse_program(&tokenize("let x = const y = 1").unwrap()).unwrap_err();
+        assert_eq!(Expected::Expression, error.expected);
+        assert_eq!("expected expression, found 'const' at 1:9", error.to_string());
+        assert_eq!(Expected::Expression, parse("1 + const").unwrap_err().expected);
+    }
+
+    #[test]
+    fn should_parse_deferred_expressions(){
+        let program = parse_program(&tokenize("defer count = count - 1\nlet x = 1").unwrap()).unwrap();
+        assert_eq!(Span::new(0, 23, 1, 1), program.statements[0].span());
+        let statements: Vec<Stmt> = program.statements.into_iter().map(unspanned_stmt).collect();
+        let decrement = assign(ident("count"), binary(ident("count"), BinaryOp::Sub, num(1.0)));
+        let expected = vec![
+            Stmt::Defer { body: Box::new(Stmt::Expr(decrement)), span: NO_SPAN },
+            Stmt::Let { constant: false, pattern: name("x"), value: num(1.0), span: NO_SPAN },
+        ];
+        assert_eq!(expected, statements);
+    }
+
+    #[test]
+    fn should_defer_a_block(){
+        let statements = parse_statements("defer { x = 1 }").unwrap();
+        let body = block_of(vec![Stmt::Expr(assign(ident("x"), num(1.0)))]);
+        assert_eq!(vec![Stmt::Defer { body: Box::new(body), span: NO_SPAN }], statements);
+        let program = parse_program(&tokenize("defer {}").unwrap()).unwrap();
+        assert_eq!(Span::new(0, 8, 1, 1), program.statements[0].span());
+    }
+
+    #[test]
+    fn should_nest_blocks(){
+        let source = "{\n  let a = 1\n  {\n    a\n    { let b = a }\n  }\n  a\n}";
+        let innermost = block_of(vec![Stmt::Let { constant: false, pattern: name("b"), value: ident("a"), span: NO_SPAN }]);
+        let middle = block_of(vec![Stmt::Expr(ident("a")), innermost]);
+        let outer = block_of(vec![
+            Stmt::Let { constant: false, pattern: name("a"), value: num(1.0), span: NO_SPAN },
+            middle,
+            Stmt::Expr(ident("a")),
+        ]);
+        assert_eq!(vec![outer], parse_statements(source).unwrap());
+
+        let program = parse_program(&tokenize(source).unwrap()).unwrap();
+        let Stmt::Block(outer) = &program.statements[0] else { panic!("expected a block, got {:?}", program.statements[0]) };
+        assert_eq!(Span::new(0, source.len(), 1, 1), outer.span());
+        assert_eq!(Span::new(16, 45, 3, 3), outer.statements[1].span());
+    }
+
+    #[test]
+    fn should_parse_empty_blocks(){
+        assert_eq!(vec![block_of(vec![])], parse_statements("{}").unwrap());
+        assert_eq!(vec![block_of(vec![block_of(vec![])]), block_of(vec![])], parse_statements("{ {} } {}").unwrap());
+    }
+
+    #[test]
+    fn should_report_where_an_unclosed_block_started(){
+        let error = parse_statements("let a = 1\n{\n  { a }\n  a").unwrap_err();
+        assert_eq!((Expected::Token(TokenType::CloseBrace), TokenType::Eof), (error.expected, error.found));
+        assert_eq!((Span::new(23, 23, 4, 4), Some(Span::new(10, 11, 2, 1))), (error.span, error.unclosed));
+        let message = "expected '}', found end of file at 4:4, unclosed block started at 2:1";
+        assert_eq!(message, error.to_string());
+        // the innermost block still open
+        let error = parse_statements("{ { {} ").unwrap_err();
+        assert_eq!(Some((1, 3)), error.unclosed.map(|open| (open.line, open.column)));
+        // a statement cut short by EOF is the statement's error
+        let error = parse_statements("{ let a =").unwrap_err();
+        assert_eq!((Expected::Expression, TokenType::Eof), (error.expected, error.found));
+        let error = parse_statements("{ a } }").unwrap_err();
+        assert_eq!((Expected::Expression, TokenType::CloseBrace), (error.expected, error.found));
+    }
+
+    #[test]
+    fn should_report_blocks_nested_too_deeply(){
+        let error = parse_statements(&"{".repeat(100_000)).unwrap_err();
+        assert_eq!("expected '}' (nested too deeply), found '{' at 1:257", error.to_string());
+        let source = format!("{}{}", "{".repeat(200), "}".repeat(200));
+        assert!(parse_statements(&source).is_ok());
+    }
+
+    #[test]
+    fn should_defer_only_expressions(){
+        let error = parse_program(&tokenize("defer let x = 1").unwrap()).unwrap_err();
+        assert_eq!("expected expression after 'defer', found 'let' at 1:7", error.to_string());
+        let error = parse_program(&tokenize("defer defer x").unwrap()).unwrap_err();
+        assert_eq!((Expected::Expression, TokenType::Defer), (error.expected, error.found));
+        let error = parse_program(&tokenize("defer").unwrap()).unwrap_err();
+        assert_eq!((Expected::Expression, TokenType::Eof), (error.expected, error.found));
+        // not an expression itself
+        assert_eq!(Expected::Expression, parse("x = defer y").unwrap_err().expected);
+    }
+
+    #[test]
+    fn should_destructure_arrays_and_objects(){
+        let expected = Pattern::Array { elements: vec![name("a"), name("b"), name("c")], span: NO_SPAN };
+        assert_eq!(expected, parse_pattern("let [a, b, c] = arr").unwrap());
+        let expected = Pattern::Object { fields: vec![field("x", name("x")), field("y", name("y"))], span: NO_SPAN };
+        assert_eq!(expected, parse_pattern("let { x, y } = point").unwrap());
+        let expected = Pattern::Object { fields: vec![field("x", name("left"))], span: NO_SPAN };
+        assert_eq!(expected, parse_pattern("let { x: left } = point").unwrap());
+        assert_eq!(Pattern::Array { elements: vec![], span: NO_SPAN }, parse_pattern("let [] = arr").unwrap());
+        assert_eq!(Pattern::Object { fields: vec![], span: NO_SPAN }, parse_pattern("let {} = obj").unwrap());
+    }
+
+    #[test]
+    fn should_destructure_nested_patterns(){
+        let inner = Pattern::Object { fields: vec![field("b", name("b"))], span: NO_SPAN };
+        let expected = Pattern::Object { fields: vec![field("a", inner)], span: NO_SPAN };
+        assert_eq!(expected, parse_pattern("let { a: { b } } = obj").unwrap());
+
+        let pair = Pattern::Array { elements: vec![name("x"), name("y")], span: NO_SPAN };
+        let points = Pattern::Array { elements: vec![pair], span: NO_SPAN };
+        let expected = Pattern::Object { fields: vec![field("points", points), field("count", name("count"))], span: NO_SPAN };
+        assert_eq!(expected, parse_pattern("let { points: [[x, y]], count } = shape").unwrap());
+
+        let program = parse_program(&tokenize("let { a: [b] } = obj").unwrap()).unwrap();
+        let Stmt::Let { pattern: Pattern::Object { fields, span }, .. } = &program.statements[0] else {
+            panic!("expected an object pattern, got {:?}", program.statements[0]);
+        };
+        assert_eq!(Span::new(4, 14, 1, 5), *span);
+        assert_eq!(Span::new(6, 12, 1, 7), fields[0].span());
+        assert_eq!(Span::new(9, 12, 1, 10), fields[0].value.span());
+    }
+
+    #[test]
+    fn should_name_the_pattern_in_errors(){
+        let error = parse_pattern("let [a, b = arr").unwrap_err();
+        assert_eq!("expected ']' in array pattern, found '=' at 1:11", error.to_string());
+        let error = parse_pattern("let { a, 1 } = obj").unwrap_err();
+        assert_eq!("expected identifier in object pattern, found number at 1:10", error.to_string());
+        assert_eq!(Some("in object pattern"), error.context);
+        assert_eq!(None, parse_pattern("let 1 = x").unwrap_err().context);
+    }
+
+    #[test]
+    fn should_report_patterns_nested_too_deeply(){
+        let error = parse_statements(&format!("let {}", "[".repeat(100_000))).unwrap_err();
+        assert_eq!("expected pattern (nested too deeply), found '[' at 1:261", error.to_string());
+        assert!(parse_pattern(&format!("let {}a{} = o", "{ a: ".repeat(200), " }".repeat(200))).is_ok());
+    }
+
+    #[test]
+    fn should_report_malformed_patterns(){
+        let error = parse_pattern("let [a, 1] = arr").unwrap_err();
+        assert_eq!(Expected::Pattern, error.expected);
+        assert_eq!((TokenType::Number, 9), (error.found, error.span.column));
+        let error = parse_pattern("let [a b] = arr").unwrap_err();
+        assert_eq!(Expected::Token(TokenType::CloseBracket), error.expected);
+        let error = parse_pattern("let [a, b,] = arr").unwrap_err();
+        assert_eq!((Expected::Pattern, TokenType::CloseBracket), (error.expected, error.found));
+        // an object field needs a name, even when it destructures further
+        let error = parse_pattern("let { [a] } = obj").unwrap_err();
+        assert_eq!(Expected::Token(TokenType::Identifier), error.expected);
+        let error = parse_pattern("let { a: } = obj").unwrap_err();
+        assert_eq!((Expected::Pattern, TokenType::CloseBrace), (error.expected, error.found));
+        let error = parse_pattern("let { a = obj").unwrap_err();
+        assert_eq!(Expected::Token(TokenType::CloseBrace), error.expected);
+    }
+
+    #[test]
+    fn should_reject_a_let_inside_an_unfinished_let(){
+        let error = parse_program(&tokenize("let a = let b = 1").unwrap()).unwrap_err();
+        assert_eq!(Expected::Expression, error.expected);
+        assert_eq!(TokenType::Let, error.found);
+        assert_eq!("expected expression, found 'let' at 1:9", error.to_string());
+        let error = parse_program(&tokenize("let a = 1 + let").unwrap()).unwrap_err();
+        assert_eq!((Expected::Expression, TokenType::Let), (error.expected, error.found));
+    }
+
+    #[test]
+    fn should_parse_tokens_lexed_without_eof(){
+        let source = "let a = 1\nlet b = (a";
+        let config = LexerConfig { emit_eof: false, ..LexerConfig::default() };
+        let tokens: Vec<Token> = Lexer::new(source).with_config(config).collect::<Result<_, _>>().unwrap();
+        let error = parse_program(&tokens).unwrap_err();
+        assert_eq!("expected ')' after expression, found end of file at 2:11", error.to_string());
+        assert_eq!(parse_program(&tokenize(source).unwrap()), Err(error));
+    }
+
+    #[test]
+    fn should_recover_from_independent_errors(){
+        let source = "let a = 1\nlet = 2\nlet b = a + 1\nlet c = (b\nconst d = 4\ndefer\nlet e = d";
+        let (program, errors) = parse_program_recovering(&tokenize(source).unwrap());
+        let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        let expected = vec![
+            "expected pattern, found '=' at 2:5",
+            "expected ')' after expression, found 'const' at 5:1",
+            "expected expression after 'defer', found 'let' at 7:1",
+        ];
+        assert_eq!(expected, messages);
+
+        let spans: Vec<Span> = program.statements.iter().map(|statement| statement.span()).collect();
+        assert_eq!(Span::new(10, 17, 2, 1), spans[1]);
+        assert_eq!(Span::new(32, 42, 4, 1), spans[3]);
+        assert_eq!(Span::new(55, 60, 6, 1), spans[5]);
+        let statements: Vec<Stmt> = program.statements.into_iter().map(unspanned_stmt).collect();
+        let expected = vec![
+            Stmt::Let { constant: false, pattern: name("a"), value: num(1.0), span: NO_SPAN },
+            Stmt::Error(NO_SPAN),
+            Stmt::Let { constant: false, pattern: name("b"), value: binary(ident("a"), BinaryOp::Add, num(1.0)), span: NO_SPAN },
+            Stmt::Error(NO_SPAN),
+            Stmt::Let { constant: true, pattern: name("d"), value: num(4.0), span: NO_SPAN },
+            Stmt::Error(NO_SPAN),
+            Stmt::Let { constant: false, pattern: name("e"), value: ident("d"), span: NO_SPAN },
+        ];
+        assert_eq!(expected, statements);
+    }
+
+    #[test]
+    fn should_skip_to_the_next_statement_keyword(){
+        // a statement failing on its first token still skips it
+        let (program, errors) = parse_program_recovering(&tokenize(") ) x\nlet y = 1 1 +").unwrap());
+        assert_eq!(2, errors.len());
+        let spans: Vec<Span> = program.statements.iter().map(|statement| statement.span()).collect();
+        assert_eq!(vec![Span::new(0, 5, 1, 1), Span::new(6, 15, 2, 1), Span::new(16, 19, 2, 11)], spans);
+        assert!(matches!(program.statements[2], Stmt::Error(_)));
+        assert_eq!(TokenType::Eof, errors[1].found);
+
+        // the same as `parse_program` without errors
+        let tokens = tokenize("let a = 1\na * 2").unwrap();
+        let (program, errors) = parse_program_recovering(&tokens);
+        assert!(errors.is_empty());
+        assert_eq!(parse_program(&tokens).unwrap(), program);
+        let (program, errors) = parse_program_recovering(&tokenize("").unwrap());
+        assert!(program.statements.is_empty() && errors.is_empty());
+    }
+
+    #[test]
+    fn should_skip_the_rest_of_a_block_after_an_error_in_it(){
+        let (program, errors) = parse_program_recovering(&tokenize("{ let = 1 let y = 2 }\nlet z = 3").unwrap());
+        let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        assert_eq!(vec!["expected pattern, found '=' at 1:7"], messages);
+        let spans: Vec<Span> = program.statements.iter().map(|statement| statement.span()).collect();
+        assert_eq!(vec![Span::new(0, 21, 1, 1), Span::new(22, 31, 2, 1)], spans);
+        assert!(matches!(program.statements[0], Stmt::Error(_)));
+
+        // blocks nested in the failed one are skipped too
+        let source = "{ { let = 1 } { x } let y = 2 }\nlet z = 3";
+        let (program, errors) = parse_program_recovering(&tokenize(source).unwrap());
+        assert_eq!(1, errors.len());
+        let statements: Vec<Stmt> = program.statements.into_iter().map(unspanned_stmt).collect();
+        let expected = vec![
+            Stmt::Error(NO_SPAN),
+            Stmt::Let { constant: false, pattern: name("z"), value: num(3.0), span: NO_SPAN },
+        ];
+        assert_eq!(expected, statements);
+    }
+
+    #[test]
+    fn should_report_nesting_too_deep_instead_of_overflowing(){
+        let source = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        let error = parse(&source).unwrap_err();
+        assert_eq!("expected expression (nested too deeply), found '(' at 1:257", error.to_string());
+        let error = parse(&format!("{}x", "!".repeat(100_000))).unwrap_err();
+        assert_eq!((Expected::Expression, TokenType::Bang), (error.expected, error.found));
+        assert!(parse(&"x ** ".repeat(100_000)).is_err());
+        assert!(parse(&"x = ".repeat(100_000)).is_err());
+
+        // the limit is on depth, not length
+        let source = format!("{}1{}", "(".repeat(200), ")".repeat(200));
+        assert_eq!(num(1.0), parse(&source).map(unspanned).unwrap());
+        assert!(parse(&format!("{}1", "1 + ".repeat(1000))).is_ok());
+    }
+
+    #[test]
+    fn should_report_a_dangling_operator(){
+        let error = parse("1 +").unwrap_err();
+        assert_eq!(Expected::Expression, error.expected);
+        assert_eq!(TokenType::Eof, error.found);
+        assert_eq!("expected expression, found end of file at 1:4", error.to_string());
+        assert_eq!("expected expression, found operator at 1:1", parse("* 2").unwrap_err().to_string());
+    }
+
+    #[test]
+    fn should_report_unbalanced_parentheses(){
+        let error = parse("(1 + 2").unwrap_err();
+        assert_eq!(Expected::Token(TokenType::CloseParen), error.expected);
+        assert_eq!(TokenType::Eof, error.found);
+        assert_eq!("expected ')' after expression, found end of file at 1:7", error.to_string());
+        let error = parse_program(&tokenize("let x = (1 + 2\nlet y = 3").unwrap()).unwrap_err();
+        assert_eq!("expected ')' after expression, found 'let' at 2:1", error.to_string());
+        assert_eq!("expected end of file after expression, found ')' at 1:6", parse("1 + 2)").unwrap_err().to_string());
+        assert_eq!(Expected::Expression, parse("()").unwrap_err().expected);
+    }
+
+    #[test]
+    fn should_reject_tokens_after_the_expression(){
+        let error = parse("1 2").unwrap_err();
+        assert_eq!(Expected::Token(TokenType::Eof), error.expected);
+        assert_eq!("expected end of file after expression, found number at 1:3", error.to_string());
+    }
+}
@@ -0,0 +1,54 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use lexer_analyzer::lexer::Lexer;
+
+// counts the allocations of a thread while it is measuring, other test threads
+// allocating at the same time are not counted
+struct CountingAllocator;
+
+thread_local! {
+    static MEASURING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if MEASURING.get() {
+            ALLOCATIONS.set(ALLOCATIONS.get() + 1);
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations_while_lexing(source: &str) -> usize {
+    ALLOCATIONS.set(0);
+    MEASURING.set(true);
+    let mut count = 0;
+    for token in Lexer::new(source) {
+        assert!(token.is_ok());
+        count += 1;
+    }
+    MEASURING.set(false);
+    assert!(count > 0);
+    ALLOCATIONS.get()
+}
+
+#[test]
+fn should_not_allocate_per_token() {
+    let small = "let x = 45 * (4 / 3)\n".repeat(10);
+    let large = "let x = 45 * (4 / 3)\n".repeat(10_000);
+    let small_allocations = allocations_while_lexing(&small);
+    let large_allocations = allocations_while_lexing(&large);
+    assert_eq!(
+        small_allocations, large_allocations,
+        "lexing 1000x more tokens should not allocate more"
+    );
+}
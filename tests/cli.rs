@@ -0,0 +1,43 @@
+#![cfg(feature = "std")]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+// runs the binary with `args`, writing `input` to its stdin
+fn run(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lexer-analyzer"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn should_tokenize_piped_stdin() {
+    let expected = "1:1 'let'\n1:5 `x` (identifier)\n1:7 '='\n1:9 `1` (number)\n2:1 end of file\n";
+    for args in [&[][..], &["-"][..]] {
+        let output = run(args, b"let x = 1\n");
+        assert!(output.status.success(), "{:?}", output);
+        assert_eq!(expected, String::from_utf8(output.stdout).unwrap());
+    }
+}
+
+#[test]
+fn should_fail_cleanly_on_invalid_utf8_stdin() {
+    let output = run(&[], b"let x\n\xff");
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("error: invalid UTF-8 at 2:1\n", String::from_utf8(output.stderr).unwrap());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn should_print_tokens_up_to_the_first_error() {
+    let output = run(&[], b"let $");
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("1:1 'let'\n", String::from_utf8(output.stdout).unwrap());
+    assert_eq!("error: unrecognized character '$' at 1:5\n", String::from_utf8(output.stderr).unwrap());
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "std")]
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use lexer_analyzer::lexer::{tokenize, tokenize_file, tokenize_reader, LexErrorKind, Span};
+
+// file in the system temp dir, unique per test
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("lexer-analyzer-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn should_tokenize_file() {
+    let path = temp_file("normal.lang", b"let x = 45\nlet y = x");
+    let tokens = tokenize_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(tokenize("let x = 45\nlet y = x").unwrap(), tokens);
+}
+
+#[test]
+fn should_report_missing_file() {
+    let path = std::env::temp_dir().join("lexer-analyzer-does-not-exist.lang");
+    let error = tokenize_file(&path).unwrap_err();
+    assert_eq!(LexErrorKind::Io(ErrorKind::NotFound), error.kind);
+    assert_eq!(Some(path.display().to_string()), error.file);
+}
+
+#[test]
+fn should_report_position_of_invalid_utf8() {
+    let path = temp_file("invalid.lang", b"let x = 1\nlet \xff = 2");
+    let error = tokenize_file(&path).unwrap_err();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(LexErrorKind::InvalidUtf8, error.kind);
+    assert_eq!(Span::new(14, 15, 2, 5), error.span);
+    assert_eq!(format!("{}:2:5: invalid UTF-8", path.display()), error.to_string());
+}
+
+#[test]
+fn should_report_file_name_for_lexing_errors() {
+    let path = temp_file("bad.lang", "let é = 1\n  @".as_bytes());
+    let error = tokenize_file(&path).unwrap_err();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(LexErrorKind::UnrecognizedCharacter('@'), error.kind);
+    assert_eq!(format!("{}:2:3: unrecognized character '@'", path.display()), error.to_string());
+}
+
+#[test]
+fn should_tokenize_any_reader() {
+    let tokens = tokenize_reader("x = 1".as_bytes()).unwrap();
+    assert_eq!(tokenize("x = 1").unwrap(), tokens);
+}
@@ -0,0 +1,37 @@
+mod support;
+
+use lexer_analyzer::lexer::{iter_tokens, tokenize, TokenType};
+
+#[test]
+fn should_generate_the_same_program_for_the_same_seed() {
+    assert_eq!(support::program(4096, 7), support::program(4096, 7));
+    assert_ne!(support::program(4096, 7), support::program(4096, 8));
+}
+
+#[test]
+fn should_tokenize_generated_programs() {
+    for seed in 1..20 {
+        let source = support::program(2048, seed);
+        assert!(source.len() >= 2048);
+        let tokens = tokenize(&source).unwrap();
+        assert!(tokens.iter().any(|token| token.kind() == TokenType::Number));
+        assert!(tokens.iter().any(|token| token.kind() == TokenType::OpenParen));
+    }
+}
+
+#[test]
+fn should_tokenize_pathological_inputs() {
+    assert_eq!(2, tokenize(&support::giant_identifier(10_000)).unwrap().len());
+    assert_eq!(2, tokenize(&support::giant_number(10_000)).unwrap().len());
+    assert_eq!(1001, tokenize(&support::single_char_tokens(1000)).unwrap().len());
+}
+
+#[test]
+fn should_agree_with_eager_tokenize() {
+    let broken = ["let x = @", "\"open", "a\nb $ c", ""];
+    let generated = (1..10).map(|seed| support::program(512, seed));
+    for source in broken.iter().map(|s| s.to_string()).chain(generated) {
+        let streamed: Result<Vec<_>, _> = iter_tokens(&source).collect();
+        assert_eq!(tokenize(&source), streamed, "{:?}", source);
+    }
+}
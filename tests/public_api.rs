@@ -0,0 +1,43 @@
+use lexer_analyzer::lexer::{keywords, tokenize, Span, Token, TokenType};
+
+#[test]
+fn should_expose_token_kinds_and_values() {
+    let tokens = tokenize("let total = (1 + 2) * null").unwrap();
+    let actual: Vec<(TokenType, &str)> = tokens.iter().map(|t| (t.kind(), t.value())).collect();
+    let expected = vec![
+        (TokenType::Let, "let"),
+        (TokenType::Identifier, "total"),
+        (TokenType::Equals, "="),
+        (TokenType::OpenParen, "("),
+        (TokenType::Number, "1"),
+        (TokenType::BinaryOperator, "+"),
+        (TokenType::Number, "2"),
+        (TokenType::CloseParen, ")"),
+        (TokenType::BinaryOperator, "*"),
+        (TokenType::Null, "null"),
+        (TokenType::Eof, ""),
+    ];
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn should_construct_tokens_outside_the_crate() {
+    let token = Token::new("42", TokenType::Number, Span::new(0, 2, 1, 1));
+    let lexed = &tokenize("42").unwrap()[0];
+    assert_eq!(token.kind(), lexed.kind());
+    assert_eq!(token.value(), lexed.value());
+    assert_eq!(token.span(), lexed.span());
+}
+
+#[test]
+fn should_lex_every_listed_keyword_into_its_kind() {
+    assert!(!keywords().is_empty());
+    for &(word, kind) in keywords() {
+        let tokens = tokenize(word).unwrap();
+        let actual: Vec<(TokenType, &str)> = tokens.iter().map(|t| (t.kind(), t.value())).collect();
+        assert_eq!(vec![(kind, word), (TokenType::Eof, "")], actual);
+        assert!(kind.is_keyword(), "{:?}", kind);
+    }
+    let listed = TokenType::all().iter().filter(|kind| kind.is_keyword()).count();
+    assert_eq!(listed, keywords().len());
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "serde")]
+
+use lexer_analyzer::lexer::{tokenize, Token, TokenType};
+
+#[test]
+fn should_round_trip_tokens_through_json() {
+    let tokens = tokenize("let x = 45 * (4 / 3)\nlet y = null").unwrap();
+    let json = serde_json::to_string(&tokens).unwrap();
+    let decoded: Vec<Token> = serde_json::from_str(&json).unwrap();
+    assert_eq!(tokens, decoded);
+}
+
+#[test]
+fn should_serialize_kinds_as_readable_names() {
+    let tokens = tokenize("(x").unwrap();
+    let json = serde_json::to_value(&tokens).unwrap();
+    assert_eq!("open_paren", json[0]["kind"]);
+    assert_eq!("identifier", json[1]["kind"]);
+    assert_eq!("eof", json[2]["kind"]);
+    assert_eq!(serde_json::json!({ "start": 1, "end": 2, "line": 1, "column": 2 }), json[1]["span"]);
+}
+
+#[test]
+fn should_serialize_every_kind_as_its_name() {
+    for kind in TokenType::all() {
+        assert_eq!(serde_json::json!(kind.name()), serde_json::to_value(kind).unwrap(), "{:?}", kind);
+        assert_eq!(*kind, serde_json::from_value::<TokenType>(serde_json::json!(kind.name())).unwrap());
+    }
+}
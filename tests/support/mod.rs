@@ -0,0 +1,74 @@
+// Deterministic source generators shared by the integration tests and the
+// benchmarks. Benches pull this in with `#[path]`, so not every user needs every
+// function.
+#![allow(dead_code)]
+
+// small xorshift generator, enough to vary the programs without a dependency
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+const NAMES: [&str; 8] = ["x", "total", "count", "someVariable", "a", "value", "result", "item"];
+const OPERATORS: [&str; 5] = ["+", "-", "*", "/", "%"];
+
+// `let` statements of identifiers, numbers, operators and parens, roughly
+// `size` bytes long; the same seed always gives the same program
+pub fn program(size: usize, seed: u64) -> String {
+    let mut rng = Rng::new(seed);
+    let mut source = String::with_capacity(size + 64);
+    while source.len() < size {
+        source.push_str("let ");
+        source.push_str(NAMES[rng.below(NAMES.len())]);
+        source.push_str(" = ");
+        expression(&mut rng, &mut source, 3);
+        source.push('\n');
+    }
+    source
+}
+
+fn expression(rng: &mut Rng, source: &mut String, depth: u32) {
+    let terms = 1 + rng.below(4);
+    for i in 0..terms {
+        if i > 0 {
+            source.push(' ');
+            source.push_str(OPERATORS[rng.below(OPERATORS.len())]);
+            source.push(' ');
+        }
+        match rng.below(4) {
+            0 if depth > 0 => {
+                source.push('(');
+                expression(rng, source, depth - 1);
+                source.push(')');
+            }
+            1 => source.push_str(NAMES[rng.below(NAMES.len())]),
+            2 => source.push_str("null"),
+            _ => source.push_str(&rng.below(100_000).to_string()),
+        }
+    }
+}
+
+// one identifier `len` letters long
+pub fn giant_identifier(len: usize) -> String {
+    "a".repeat(len)
+}
+
+// one number `len` digits long
+pub fn giant_number(len: usize) -> String {
+    "7".repeat(len)
+}
+
+// `count` single character tokens with no whitespace between them
+pub fn single_char_tokens(count: usize) -> String {
+    ["(", ")", "+", "*", "="].iter().cycle().take(count).copied().collect()
+}